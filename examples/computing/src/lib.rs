@@ -6,7 +6,10 @@ use near_sdk::{
     env, ext_contract, near_bindgen, AccountId, Balance, BorshStorageKey, Gas, PanicOnDefault,
     PromiseOrValue, PromiseResult,
 };
-use protocol_sdk::{Content, Context, OmniChain, Payload, Value};
+use protocol_sdk::{
+    cross_chain_interface, Content, Context, OmniChain, Payload, SessionError, Value,
+    VersionedOmniChain,
+};
 
 const GAS_FOR_CALLBACK: Gas = Gas(5_000_000_000_000);
 
@@ -17,12 +20,13 @@ const NO_DEPOSIT: Balance = 0;
 pub struct ComputeTask {
     pub nums: Vec<u32>,
     pub result: Option<u32>,
+    pub error: Option<String>,
 }
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct Computation {
-    omni_chain: OmniChain,
+    omni_chain: VersionedOmniChain,
     compute_task: UnorderedMap<(String, u128), ComputeTask>,
 }
 
@@ -31,6 +35,12 @@ pub trait MyContract {
     fn callback(&mut self, to_chain: String, nums: Vec<u32>) -> U128;
 }
 
+#[cross_chain_interface]
+pub trait ComputeService {
+    #[callback = "receive_compute_result"]
+    fn receive_compute_task(&self, nums: Vec<u32>) -> u32;
+}
+
 #[derive(BorshSerialize, BorshStorageKey)]
 enum StorageKey {
     DestinationContract,
@@ -48,31 +58,14 @@ impl Computation {
                 StorageKey::DestinationContract,
                 StorageKey::PermittedContract,
                 omni_chain_contract_id,
-            ),
+            )
+            .into(),
             compute_task: UnorderedMap::new(StorageKey::Result),
         }
     }
 
     pub fn send_compute_task(&mut self, to_chain: String, nums: Vec<u32>) -> PromiseOrValue<U128> {
-        let mut payload = Payload::new();
-        payload.push_item("nums".to_string(), Value::VecUint32(nums.clone()));
-        let action_name = "receive_compute_task".to_string();
-        let dst_contract = self
-            .omni_chain
-            .destination_contract
-            .get(&to_chain.clone())
-            .expect("to chain not register");
-        let contract = dst_contract
-            .get(&action_name)
-            .expect("contract not register");
-        let content = Content {
-            contract: contract.contract_address.clone(),
-            action: contract.action_name.clone(),
-            data: payload,
-        };
-        let callback = "receive_compute_result".as_bytes().to_vec();
-        self.omni_chain
-            .call_cross_with_session(to_chain.clone(), content, callback)
+        compute_service::receive_compute_task(&mut self.omni_chain, to_chain.clone(), nums.clone())
             .then(ext_self::callback(
                 to_chain,
                 nums,
@@ -83,18 +76,48 @@ impl Computation {
             .into()
     }
 
-    pub fn receive_compute_task(&self, payload: Payload, context: Context) {
-        assert_eq!(
-            self.omni_chain.omni_chain_contract_id,
-            env::predecessor_account_id(),
-            "Processs by cross chain contract"
-        );
-        self.omni_chain.assert_register_permitted_contract(
-            &context.from_chain,
-            &context.sender,
-            &context.action,
-        );
+    pub fn get_compute_task(&self, to_chain: String, id: U128) -> Option<ComputeTask> {
+        self.compute_task.get(&(to_chain, id.0))
+    }
+
+    #[private]
+    pub fn callback(&mut self, to_chain: String, nums: Vec<u32>) -> U128 {
+        // let mut session_id: u64 = 0;
+        match env::promise_result(0) {
+            PromiseResult::Successful(result) => {
+                let session_id = near_sdk::serde_json::from_slice::<Option<U128>>(&result)
+                    .expect("unwrap session id failed")
+                    .expect("call omi-chain failed");
+                self.compute_task.insert(
+                    &(to_chain, session_id.0),
+                    &ComputeTask {
+                        nums,
+                        result: None,
+                        error: None,
+                    },
+                );
+                session_id
+            }
+            _ => env::panic_str("call omi-chain failed"),
+        }
+    }
+
+    pub fn get_dst_contract(&self, chain: String, action_name: String) -> (Vec<u8>, Vec<u8>) {
+        let contract = self.omni_chain.get_dst_contract(&chain, &action_name);
+        (contract.contract_address, contract.action_name)
+    }
+
+    pub fn get_permitted_contract(&self) -> Vec<(String, Vec<u8>, String)> {
+        self.omni_chain.permitted_contract_index.to_vec()
+    }
+
+    pub fn clear_compute_task(&mut self) {
+        self.compute_task.clear();
+    }
+}
 
+impl Computation {
+    fn receive_compute_task(&mut self, payload: Payload, context: Context) {
         let item = payload.get_item("nums".to_string()).unwrap();
         let nums = item.get_value::<Vec<u32>>().unwrap();
 
@@ -104,7 +127,7 @@ impl Computation {
         }
 
         let mut payload = Payload::new();
-        payload.push_item("result".to_string(), Value::Uint32(sum));
+        payload.set_item("result".to_string(), Value::Uint32(sum));
         let content = Content {
             contract: context.sender,
             action: context.session.callback.unwrap(),
@@ -114,12 +137,7 @@ impl Computation {
             .send_response_message(context.from_chain, content, context.session.id);
     }
 
-    pub fn receive_compute_result(&mut self, payload: Payload, context: Context) {
-        assert_eq!(
-            self.omni_chain.omni_chain_contract_id,
-            env::predecessor_account_id(),
-            "Processs by cross chain contract."
-        );
+    fn receive_compute_result(&mut self, payload: Payload, context: Context) {
         let item = payload.get_item("result".to_string()).unwrap();
         let result = item.get_value::<u32>().unwrap();
         let session = context.session;
@@ -131,48 +149,24 @@ impl Computation {
         });
     }
 
-    pub fn get_compute_task(&self, to_chain: String, id: U128) -> Option<ComputeTask> {
-        self.compute_task.get(&(to_chain, id.0))
-    }
-
-    #[private]
-    pub fn callback(&mut self, to_chain: String, nums: Vec<u32>) -> U128 {
-        // let mut session_id: u64 = 0;
-        match env::promise_result(0) {
-            PromiseResult::Successful(result) => {
-                let session_id = near_sdk::serde_json::from_slice::<U128>(&result)
-                    .expect("unwrap session id failed");
-                self.compute_task.insert(
-                    &(to_chain, session_id.0),
-                    &ComputeTask { nums, result: None },
-                );
-                session_id
-            }
-            _ => env::panic_str("call omi-chain failed"),
-        }
-    }
-
-    pub fn get_dst_contract(&self, chain: String, action_name: String) -> (Vec<u8>, Vec<u8>) {
-        let dst_contract = self.omni_chain.destination_contract.get(&chain).unwrap();
-        // let action_name = "receive_"
-        let contract = dst_contract.get(&action_name).unwrap();
-        (
-            contract.contract_address.clone(),
-            contract.action_name.clone(),
-        )
-    }
-    // UnorderedMap<(String, String), Vec<String>>,
-    pub fn get_permitted_contract(&self) -> Vec<((String, Vec<u8>), Vec<String>)> {
-        self.omni_chain
-            .permitted_contract
-            .iter()
-            .map(|res| res)
-            .collect()
-    }
-
-    pub fn clear_compute_task(&mut self) {
-        self.compute_task.clear();
+    fn receive_compute_error(&mut self, error: SessionError, context: Context) {
+        let key = (context.from_chain, context.session.id.0);
+        self.compute_task.get(&key).as_mut().and_then(|task| {
+            task.error = Some(error.message);
+            self.compute_task.insert(&key, task)
+        });
     }
 }
 
 protocol_sdk::impl_omni_chain_register!(Computation, omni_chain);
+protocol_sdk::impl_omni_chain_migrate!(
+    Computation,
+    omni_chain,
+    StorageKey::DestinationContract,
+    StorageKey::PermittedContract
+);
+protocol_sdk::impl_omni_chain_payload_limit!(Computation, omni_chain);
+protocol_sdk::impl_omni_chain_router!(Computation, omni_chain, {
+    "receive_compute_task" => receive_compute_task,
+    "receive_compute_result" => receive_compute_result,
+}, on_error => receive_compute_error);
@@ -2,8 +2,8 @@ use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::UnorderedMap;
 use near_sdk::json_types::U128;
 use near_sdk::serde::{Deserialize, Serialize};
-use near_sdk::{env, near_bindgen, AccountId, BorshStorageKey, PanicOnDefault, PromiseOrValue};
-use protocol_sdk::{Content, Context, OmniChain, Payload, Value};
+use near_sdk::{near_bindgen, AccountId, BorshStorageKey, PanicOnDefault, PromiseOrValue};
+use protocol_sdk::{Content, Context, OmniChain, Payload, Value, VersionedOmniChain};
 
 #[derive(Clone, PartialEq, BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
 #[serde(crate = "near_sdk::serde")]
@@ -17,7 +17,7 @@ pub struct GreetingData {
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct Greeting {
-    omni_chain: OmniChain,
+    omni_chain: VersionedOmniChain,
     greeting_data: UnorderedMap<(String, u128), GreetingData>,
 }
 
@@ -38,7 +38,8 @@ impl Greeting {
                 StorageKey::DestinationContract,
                 StorageKey::PermittedContract,
                 omni_chain_contract_id,
-            ),
+            )
+            .into(),
             greeting_data: UnorderedMap::new(StorageKey::GreetingData),
         }
     }
@@ -51,7 +52,7 @@ impl Greeting {
      * @param title - greeting date
      */
     pub fn send_greeting(
-        &self,
+        &mut self,
         to_chain: String,
         title: String,
         content: String,
@@ -59,37 +60,29 @@ impl Greeting {
     ) -> PromiseOrValue<U128> {
         let mut payload = Payload::new();
         let greeting_data = Value::VecString(vec!["NEARTEST".to_string(), title, content, date]);
-        payload.push_item("greeting".to_string(), greeting_data);
+        payload.set_item("greeting".to_string(), greeting_data);
         let action_name = "send_greeting".to_string();
-        let dst_contract = self
-            .omni_chain
-            .destination_contract
-            .get(&to_chain)
-            .expect("to chain not register");
-        let contract = dst_contract
-            .get(&action_name)
-            .expect("contract not register");
+        let contract = self.omni_chain.get_dst_contract(&to_chain, &action_name);
         let content = Content {
-            contract: contract.contract_address.clone(),
-            action: contract.action_name.clone(),
+            contract: contract.contract_address,
+            action: contract.action_name,
             data: payload,
         };
         self.omni_chain.call_cross(to_chain, content).into()
     }
 
-    pub fn receive_greeting(&mut self, payload: Payload, context: Context) {
-        assert_eq!(
-            env::predecessor_account_id(),
-            self.omni_chain.omni_chain_contract_id,
-            "Processs by cross chain contract"
-        );
-        self.omni_chain.assert_register_permitted_contract(
-            &context.from_chain,
-            &context.sender,
-            &context.action,
-        );
-        let item = payload.get_item("greeting".to_string()).unwrap();
-        let greeting = item.get_value::<Vec<String>>().unwrap();
+    pub fn get_greeting(&self, from_chain: String, id: U128) -> Option<GreetingData> {
+        self.greeting_data.get(&(from_chain, id.0))
+    }
+
+    pub fn clear_greeting_data(&mut self) {
+        self.greeting_data.clear();
+    }
+}
+
+impl Greeting {
+    fn receive_greeting(&mut self, payload: Payload, context: Context) {
+        let greeting = payload.get::<Vec<String>>("greeting").unwrap();
         let data = GreetingData {
             from_chain: greeting[0].clone(),
             title: greeting[1].clone(),
@@ -99,14 +92,16 @@ impl Greeting {
         self.greeting_data
             .insert(&(greeting[0].clone(), context.id.0), &data);
     }
-
-    pub fn get_greeting(&self, from_chain: String, id: U128) -> Option<GreetingData> {
-        self.greeting_data.get(&(from_chain, id.0))
-    }
-
-    pub fn clear_greeting_data(&mut self) {
-        self.greeting_data.clear();
-    }
 }
 
 protocol_sdk::impl_omni_chain_register!(Greeting, omni_chain);
+protocol_sdk::impl_omni_chain_migrate!(
+    Greeting,
+    omni_chain,
+    StorageKey::DestinationContract,
+    StorageKey::PermittedContract
+);
+protocol_sdk::impl_omni_chain_schema!(Greeting, omni_chain);
+protocol_sdk::impl_omni_chain_router!(Greeting, omni_chain, {
+    "receive_greeting" => receive_greeting,
+});
@@ -1,14 +1,119 @@
-use crate::types::{Content, DstContract, Session};
+use crate::events;
+use crate::NearHasher;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::UnorderedMap;
-use near_sdk::json_types::U128;
-use near_sdk::{env, ext_contract, AccountId, Balance, Gas, IntoStorageKey, Promise};
-use std::collections::HashMap;
+use near_sdk::collections::{LookupMap, LookupSet, UnorderedMap, UnorderedSet};
+use near_sdk::json_types::U64;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::serde_json::{self, json};
+use near_sdk::{
+    env, ext_contract, AccountId, Balance, BorshStorageKey, Gas, IntoStorageKey, Promise,
+    PromiseResult,
+};
+use protocol_types::{
+    Content, Context, DstContract, Envelope, Fragment, Payload, PayloadSchema, Session,
+    SessionError, Value, SEQUENCE_ITEM, U128,
+};
 
 const GAS_FOR_SENT_MESSAGE: Gas = Gas(5_000_000_000_000);
 
+const GAS_FOR_SENT_CALLBACK: Gas = Gas(10_000_000_000_000);
+
+/// Gas added per byte of the encoded content to `send_message` and to its callback, which
+/// both read and store the content.
+const GAS_PER_CONTENT_BYTE: Gas = Gas(50_000_000);
+
+const GAS_FOR_MULTICAST_CALLBACK: Gas = Gas(10_000_000_000_000);
+
+/// Gas attached to the aggregation callback of a scatter-gather request.
+const GAS_FOR_GATHER_CALLBACK: Gas = Gas(30_000_000_000_000);
+
+/// Gas used by `on_scatter_sent` besides the gas attached to the aggregation callback: its
+/// own execution and the fee of the receipt of the callback.
+const GAS_FOR_SCATTER_RESULT: Gas = Gas(15_000_000_000_000);
+
+/// Gas attached to `on_scatter_sent`, which calls the aggregation callback when no request
+/// could be sent.
+pub const GAS_FOR_SCATTER_CALLBACK: Gas = Gas(GAS_FOR_GATHER_CALLBACK.0 + GAS_FOR_SCATTER_RESULT.0);
+
+pub(crate) const DEFAULT_MAX_SEND_ATTEMPTS: u32 = 3;
+
+/// Max number of fragments of a chunked transfer, so the fragments of a transfer can be
+/// reassembled or discarded within the gas of one call.
+pub const MAX_TRANSFER_FRAGMENTS: u32 = 256;
+
+/// Nanoseconds after its first fragment a partial chunked transfer may be discarded.
+pub(crate) const DEFAULT_CHUNK_TRANSFER_TIMEOUT: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+/// Max number of messages an ordered channel receives ahead of the next expected one, so
+/// the buffered messages of a channel can be dispatched or dropped within one call.
+pub const INBOUND_REORDER_WINDOW: u64 = 64;
+
 const NO_DEPOSIT: Balance = 0;
 
+/// Gas kept by `on_cross_chain_message` for itself, the rest is shared by the dispatches.
+const GAS_FOR_ACCEPT_MESSAGE: Gas = Gas(10_000_000_000_000);
+
+/// Encoded size of the error response `on_message_dispatched` sends when a handler fails,
+/// the gas of its callback covers sending up to this size.
+const MAX_ERROR_RESPONSE_SIZE: usize = 1024;
+
+/// Gas used by `on_message_dispatched` besides the gas attached to the error response: its
+/// own execution and the fees of the receipts of the response.
+const GAS_FOR_DISPATCH_RESULT: Gas = Gas(25_000_000_000_000);
+
+/// Gas attached to `on_message_dispatched`, enough to send an error response and run its
+/// sent callback.
+pub const GAS_FOR_DISPATCH_CALLBACK: Gas = {
+    let (send_gas, callback_gas) = OmniChain::gas_for_send(MAX_ERROR_RESPONSE_SIZE);
+    Gas(send_gas.0 + callback_gas.0 + GAS_FOR_DISPATCH_RESULT.0)
+};
+
+/// Methods generated by `impl_omni_chain_router`, running the handler of a message and
+/// checking its result.
+const DISPATCH_METHOD: &str = "dispatch_cross_chain_message";
+const DISPATCH_CALLBACK_METHOD: &str = "on_cross_chain_message_dispatched";
+
+/// Prefix of the storage keys of the collections `OmniChain` creates itself, to keep them
+/// apart from the keys of the contract.
+const STORAGE_KEY_PREFIX: &[u8] = b"omni";
+
+#[derive(BorshSerialize, BorshStorageKey)]
+pub(crate) enum StorageKey {
+    ActionSchema,
+    MaxPayloadSize,
+    ChunkTransfer,
+    ChunkFragment,
+    OutboundSequence,
+    InboundSequence,
+    SequenceBuffer,
+    OutboundMessage,
+    FailedSend,
+    InboundMessage,
+    DeadLetter,
+    Gather,
+    GatherMessage,
+    DstRoute,
+    DstCandidates,
+    PermissionLimit,
+    InboundRateLimit,
+    OutboundRateLimit,
+    InboundRateUsage,
+    OutboundRateUsage,
+    DstContractIndex,
+    PermittedContractIndex,
+    OutboundByChain,
+    OutboundChainCount,
+    OutboundBySender,
+    OutboundSenderCount,
+    SequenceBuffered,
+}
+
+impl StorageKey {
+    pub(crate) fn prefixed(self) -> Vec<u8> {
+        [STORAGE_KEY_PREFIX, &self.into_storage_key()].concat()
+    }
+}
+
 #[ext_contract(ext_cross_contract)]
 pub trait OmniChainContract {
     fn send_message(
@@ -19,12 +124,259 @@ pub trait OmniChainContract {
     ) -> u128;
 }
 
+#[ext_contract(ext_omni_chain_self)]
+pub trait OmniChainSelf {
+    fn on_message_sent(
+        &mut self,
+        sender: AccountId,
+        to_chain: String,
+        content: Content,
+        session: Option<Session>,
+        attempts: u32,
+    ) -> Option<U128>;
+
+    fn on_multicast_sent(&self, chains: Vec<String>, unregistered: Vec<String>) -> MulticastResult;
+
+    fn on_scatter_sent(&mut self, correlation_id: u64, chains: Vec<String>);
+}
+
+/// To chain and action name a destination contract is registered for.
+pub type DstKey = (String, String);
+
+/// From chain, sender and action name of a permission.
+pub type PermissionKey = (String, Vec<u8>, String);
+
+/// From chain, sender and transfer id of a chunked transfer.
+pub type TransferKey = (String, Vec<u8>, [u8; 32]);
+
+/// Chain and contract address on the other side of an ordered channel.
+pub type ChannelKey = (String, Vec<u8>);
+
+/// To chain and id of a sent message, the cross-chain contract numbers the messages of
+/// each chain apart.
+pub type OutboundKey = (String, u128);
+
+/// Progress of a chunked transfer being reassembled.
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct ChunkTransfer {
+    pub action: String,
+    pub total: u32,
+    /// Indices of the fragments stored, in the order they arrived.
+    pub received: Vec<u32>,
+    pub hash: [u8; 32],
+    /// Block timestamp of the first fragment received.
+    pub started_at: u64,
+}
+
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum OutboundStatus {
+    /// Request waiting for its response.
+    Pending,
+    /// Message not expecting a response.
+    Sent,
+    Responded,
+    /// Request answered with an error response.
+    Failed,
+    /// Request without response within the response timeout.
+    TimedOut,
+}
+
+/// A message accepted by the cross-chain contract.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OutboundMessage {
+    pub sender: AccountId,
+    pub to_chain: String,
+    pub contract: Vec<u8>,
+    pub action: Vec<u8>,
+    pub session_type: Option<u8>,
+    pub payload_hash: Vec<u8>,
+    pub timestamp: U64,
+    pub status: OutboundStatus,
+}
+
+/// Ids of the messages sent by `call_cross_multi`, and the chains it failed to send to.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MulticastResult {
+    pub message_ids: Vec<(String, U128)>,
+    pub failed_chains: Vec<String>,
+}
+
+/// A message the cross-chain contract failed to send.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FailedSend {
+    pub sender: AccountId,
+    pub to_chain: String,
+    pub content: Content,
+    pub session: Option<Session>,
+    pub timestamp: U64,
+    /// Number of times the message failed to be sent.
+    pub attempts: u32,
+}
+
+/// The response of one chain to a scatter-gather request, or the error if the request
+/// failed.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GatherResponse {
+    pub chain: String,
+    pub payload: Option<Payload>,
+    pub error: Option<SessionError>,
+}
+
+/// A request sent to several chains, collecting the responses until the quorum is
+/// reached, all chains responded, or the deadline passed.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Gather {
+    /// Method of the contract called with the responses.
+    pub callback: String,
+    pub chains: Vec<String>,
+    /// Number of successful responses enough to call the callback.
+    pub quorum: u32,
+    pub deadline: U64,
+    /// Chains and ids of the requests sent.
+    pub message_ids: Vec<(String, U128)>,
+    pub responses: Vec<GatherResponse>,
+}
+
+/// One of several contracts handling an action on another chain. Messages go to the
+/// healthy contract with the lowest priority.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DstCandidate {
+    pub contract: DstContract,
+    pub priority: u32,
+    pub healthy: bool,
+}
+
+/// Limits of the permission of a contract of another chain to call an action. Permissions
+/// without limits are permanent and unlimited.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PermissionLimit {
+    /// Block timestamp from which the permission is expired.
+    pub expires_at: Option<U64>,
+    pub remaining_calls: Option<u64>,
+}
+
+/// At most `max_messages` messages in each window of `window` nanoseconds.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RateLimit {
+    pub max_messages: u32,
+    pub window: U64,
+}
+
+/// Messages counted in the current window of a rate limit.
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct RateUsage {
+    pub window_start: u64,
+    pub count: u32,
+}
+
+/// A permission or denial for the senders and actions it matches, `None` matching any
+/// chain, sender or action. The matching rule with the most fields set wins, a deny rule
+/// winning over an allow rule with as many fields set. Permissions registered with
+/// `register_permitted_contract` count as allow rules with all fields set, and keep
+/// their limits when an allow rule matches too.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PermissionRule {
+    /// Id given by `add_permission_rule`, to remove the rule with.
+    pub id: U64,
+    pub chain_name: Option<String>,
+    pub sender: Option<Vec<u8>>,
+    pub action_name: Option<String>,
+    pub allow: bool,
+}
+
+impl PermissionRule {
+    fn matches(&self, chain_name: &String, sender: &Vec<u8>, action: &String) -> bool {
+        self.chain_name.iter().all(|rule| rule == chain_name)
+            && self.sender.iter().all(|rule| rule == sender)
+            && self.action_name.iter().all(|rule| rule == action)
+    }
+
+    fn same_fields(&self, other: &PermissionRule) -> bool {
+        self.chain_name == other.chain_name
+            && self.sender == other.sender
+            && self.action_name == other.action_name
+            && self.allow == other.allow
+    }
+
+    fn specificity(&self) -> usize {
+        self.chain_name.is_some() as usize
+            + self.sender.is_some() as usize
+            + self.action_name.is_some() as usize
+    }
+}
+
+/// An inbound message whose handler failed, kept for the owner to replay or discard.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DeadLetter {
+    pub payload: Payload,
+    pub context: Context,
+    pub reason: String,
+    pub timestamp: U64,
+}
+
 #[derive(BorshDeserialize, BorshSerialize, Debug)]
 pub struct OmniChain {
     pub owner_id: AccountId,
     pub omni_chain_contract_id: AccountId,
-    pub destination_contract: UnorderedMap<String, HashMap<String, DstContract>>,
-    pub permitted_contract: UnorderedMap<(String, Vec<u8>), Vec<String>>,
+    pub destination_contract: LookupMap<DstKey, DstContract>,
+    pub permitted_contract: LookupSet<PermissionKey>,
+    pub action_schema: UnorderedMap<String, PayloadSchema>,
+    pub max_payload_size: UnorderedMap<String, u32>,
+    pub chunk_transfer: UnorderedMap<TransferKey, ChunkTransfer>,
+    pub chunk_fragment: LookupMap<(TransferKey, u32), Vec<u8>>,
+    pub outbound_sequence: UnorderedMap<ChannelKey, u64>,
+    pub inbound_sequence: UnorderedMap<ChannelKey, u64>,
+    pub sequence_buffer: LookupMap<(ChannelKey, u64), (Payload, Context)>,
+    pub outbound_message: UnorderedMap<OutboundKey, OutboundMessage>,
+    pub failed_send: UnorderedMap<u64, FailedSend>,
+    pub next_failed_send_id: u64,
+    pub outbound_log_enabled: bool,
+    pub response_timeout: Option<u64>,
+    pub max_send_attempts: u32,
+    pub dead_letter_enabled: bool,
+    pub next_inbound_id: u64,
+    pub inbound_message: LookupMap<u64, (Payload, Context)>,
+    pub dead_letter: UnorderedMap<u64, DeadLetter>,
+    pub next_correlation_id: u64,
+    pub gather: UnorderedMap<u64, Gather>,
+    pub gather_message: LookupMap<OutboundKey, u64>,
+    pub dst_route: UnorderedMap<String, Vec<(String, DstContract)>>,
+    pub dst_candidates: UnorderedMap<(String, String), Vec<DstCandidate>>,
+    pub permission_limit: UnorderedMap<(String, Vec<u8>, String), PermissionLimit>,
+    pub inbound_rate_limit: UnorderedMap<String, RateLimit>,
+    pub outbound_rate_limit: UnorderedMap<String, RateLimit>,
+    /// Usage of the inbound rate limit by each sender of a chain, one entry per sender
+    /// overwritten when its window expires.
+    pub inbound_rate_usage: LookupMap<ChannelKey, RateUsage>,
+    /// Usage of the outbound rate limit by each account sending to a chain.
+    pub outbound_rate_usage: LookupMap<(AccountId, String), RateUsage>,
+    pub permission_rules: Vec<PermissionRule>,
+    pub dst_contract_index: UnorderedSet<DstKey>,
+    pub permitted_contract_index: UnorderedSet<PermissionKey>,
+    pub next_transfer_nonce: u64,
+    pub chunk_transfer_timeout: u64,
+    /// Ids of the logged messages sent to each chain, by position.
+    pub outbound_by_chain: LookupMap<(String, u64), u128>,
+    pub outbound_chain_count: LookupMap<String, u64>,
+    /// Ids of the logged messages sent by each account, by position.
+    pub outbound_by_sender: LookupMap<(AccountId, u64), OutboundKey>,
+    pub outbound_sender_count: LookupMap<AccountId, u64>,
+    /// Sequence numbers of the messages buffered on each ordered channel.
+    pub sequence_buffered: LookupMap<ChannelKey, Vec<u64>>,
+    pub next_rule_id: u64,
 }
 
 impl OmniChain {
@@ -41,34 +393,631 @@ impl OmniChain {
         let this = Self {
             owner_id,
             omni_chain_contract_id,
-            destination_contract: UnorderedMap::new(destination_contract_prefix),
-            permitted_contract: UnorderedMap::new(permitted_contract_prefix),
+            destination_contract: LookupMap::new(destination_contract_prefix),
+            permitted_contract: LookupSet::new(permitted_contract_prefix),
+            action_schema: UnorderedMap::new(StorageKey::ActionSchema.prefixed()),
+            max_payload_size: UnorderedMap::new(StorageKey::MaxPayloadSize.prefixed()),
+            chunk_transfer: UnorderedMap::new(StorageKey::ChunkTransfer.prefixed()),
+            chunk_fragment: LookupMap::new(StorageKey::ChunkFragment.prefixed()),
+            outbound_sequence: UnorderedMap::new(StorageKey::OutboundSequence.prefixed()),
+            inbound_sequence: UnorderedMap::new(StorageKey::InboundSequence.prefixed()),
+            sequence_buffer: LookupMap::new(StorageKey::SequenceBuffer.prefixed()),
+            outbound_message: UnorderedMap::new(StorageKey::OutboundMessage.prefixed()),
+            failed_send: UnorderedMap::new(StorageKey::FailedSend.prefixed()),
+            next_failed_send_id: 0,
+            outbound_log_enabled: false,
+            response_timeout: None,
+            max_send_attempts: DEFAULT_MAX_SEND_ATTEMPTS,
+            dead_letter_enabled: false,
+            next_inbound_id: 0,
+            inbound_message: LookupMap::new(StorageKey::InboundMessage.prefixed()),
+            dead_letter: UnorderedMap::new(StorageKey::DeadLetter.prefixed()),
+            next_correlation_id: 0,
+            gather: UnorderedMap::new(StorageKey::Gather.prefixed()),
+            gather_message: LookupMap::new(StorageKey::GatherMessage.prefixed()),
+            dst_route: UnorderedMap::new(StorageKey::DstRoute.prefixed()),
+            dst_candidates: UnorderedMap::new(StorageKey::DstCandidates.prefixed()),
+            permission_limit: UnorderedMap::new(StorageKey::PermissionLimit.prefixed()),
+            inbound_rate_limit: UnorderedMap::new(StorageKey::InboundRateLimit.prefixed()),
+            outbound_rate_limit: UnorderedMap::new(StorageKey::OutboundRateLimit.prefixed()),
+            inbound_rate_usage: LookupMap::new(StorageKey::InboundRateUsage.prefixed()),
+            outbound_rate_usage: LookupMap::new(StorageKey::OutboundRateUsage.prefixed()),
+            permission_rules: Vec::new(),
+            dst_contract_index: UnorderedSet::new(StorageKey::DstContractIndex.prefixed()),
+            permitted_contract_index: UnorderedSet::new(
+                StorageKey::PermittedContractIndex.prefixed(),
+            ),
+            next_transfer_nonce: 0,
+            chunk_transfer_timeout: DEFAULT_CHUNK_TRANSFER_TIMEOUT,
+            outbound_by_chain: LookupMap::new(StorageKey::OutboundByChain.prefixed()),
+            outbound_chain_count: LookupMap::new(StorageKey::OutboundChainCount.prefixed()),
+            outbound_by_sender: LookupMap::new(StorageKey::OutboundBySender.prefixed()),
+            outbound_sender_count: LookupMap::new(StorageKey::OutboundSenderCount.prefixed()),
+            sequence_buffered: LookupMap::new(StorageKey::SequenceBuffered.prefixed()),
+            next_rule_id: 0,
         };
         this
     }
 
+    /**
+     * Send a message, through the forwarders of the route registered for the chain if
+     * any. The payload size and the rate of the sender are checked against the chain the
+     * message is for, and the envelope sent also against the payload size of the first
+     * hop. Responses can't find their way back through
+     * forwarders, so messages with session can't be sent to a chain with a route
+     * @param to_chain - to chain name
+     * @param content - message content
+     * @param session - session of the message
+     */
     pub fn internal_call_omni_chain(
+        &mut self,
+        to_chain: String,
+        content: Content,
+        session: Option<Session>,
+    ) -> Promise {
+        let sender = env::predecessor_account_id();
+        self.assert_payload_size(&to_chain, &content);
+        self.assert_outbound_rate(&sender, &to_chain);
+        let hops = self.dst_route.get(&to_chain).unwrap_or_default();
+        assert!(
+            hops.is_empty() || session.is_none(),
+            "Session to routed chain"
+        );
+        let routed = !hops.is_empty();
+        let (to_chain, content) = Self::route_content(hops, to_chain, content);
+        if routed {
+            self.assert_payload_size(&to_chain, &content);
+        }
+        self.send_message(sender, to_chain, content, session, 0, None)
+    }
+
+    /// Gas attached to `send_message` and to its callback for a content of `size` bytes.
+    pub(crate) const fn gas_for_send(size: usize) -> (Gas, Gas) {
+        let content_gas = GAS_PER_CONTENT_BYTE.0 * size as u64;
+        (
+            Gas(GAS_FOR_SENT_MESSAGE.0 + content_gas),
+            Gas(GAS_FOR_SENT_CALLBACK.0 + content_gas),
+        )
+    }
+
+    fn send_message(
         &self,
+        sender: AccountId,
         to_chain: String,
         content: Content,
         session: Option<Session>,
+        attempts: u32,
+        gas: Option<Gas>,
     ) -> Promise {
+        let size = content.encoded_size();
+        let (send_gas, callback_gas) = Self::gas_for_send(size);
+        let send_gas = gas.unwrap_or(send_gas);
+        let available = env::prepaid_gas().0.saturating_sub(env::used_gas().0);
+        assert!(
+            available >= send_gas.0 + callback_gas.0,
+            "Not enough gas to send {} bytes",
+            size
+        );
         ext_cross_contract::send_message(
+            to_chain.clone(),
+            content.clone(),
+            session.clone(),
+            self.omni_chain_contract_id.clone(),
+            NO_DEPOSIT,
+            send_gas,
+        )
+        .then(ext_omni_chain_self::on_message_sent(
+            sender,
             to_chain,
             content,
             session,
-            self.omni_chain_contract_id.clone(),
+            attempts,
+            env::current_account_id(),
             NO_DEPOSIT,
-            GAS_FOR_SENT_MESSAGE,
+            callback_gas,
+        ))
+    }
+
+    /**
+     * Send a failed message again, the failed message is removed and recorded again if
+     * it fails anew. Callable by the owner or the account which sent the message. The
+     * payload size and the rate of the sender are checked again, against the current limits
+     * @param failed_id - id of the failed message
+     * @param gas - gas attached to `send_message` of the cross-chain contract, the one
+     * for the size of the content if none
+     */
+    pub fn retry_message(&mut self, failed_id: u64, gas: Option<Gas>) -> Promise {
+        let failed = self
+            .failed_send
+            .get(&failed_id)
+            .expect("failed message not exist");
+        let predecessor = env::predecessor_account_id();
+        assert!(
+            predecessor == failed.sender || predecessor == self.owner_id,
+            "Unauthorize"
+        );
+        assert!(
+            failed.attempts < self.max_send_attempts,
+            "Max attempts reached"
+        );
+        let (to_chain, content) = Self::final_destination(&failed.to_chain, &failed.content);
+        self.assert_payload_size(&to_chain, &content);
+        self.assert_outbound_rate(&failed.sender, &to_chain);
+        self.failed_send.remove(&failed_id);
+        events::emit(
+            "send_retried",
+            json!({ "failed_id": U64(failed_id), "attempt": failed.attempts + 1 }),
+        );
+        self.send_message(
+            failed.sender,
+            failed.to_chain,
+            failed.content,
+            failed.session,
+            failed.attempts,
+            gas,
         )
     }
 
-    pub fn call_cross(&self, to_chain: String, content: Content) -> Promise {
+    /**
+     * Remove a failed message which won't be sent again. Messages of ordered channels can
+     * only be discarded by the owner, as the receiver waits for them until its owner skips
+     * them with `skip_inbound_sequence`
+     * @param failed_id - id of the failed message
+     */
+    pub fn discard_failed_message(&mut self, failed_id: u64) {
+        let failed = self
+            .failed_send
+            .get(&failed_id)
+            .expect("failed message not exist");
+        let predecessor = env::predecessor_account_id();
+        if failed.content.data.contains(SEQUENCE_ITEM) {
+            assert_eq!(predecessor, self.owner_id, "Message of an ordered channel");
+        } else {
+            assert!(
+                predecessor == failed.sender || predecessor == self.owner_id,
+                "Unauthorize"
+            );
+        }
+        self.failed_send.remove(&failed_id);
+    }
+
+    /**
+     * Set how many times a message may fail to be sent before it can't be retried
+     * @param max_attempts - max number of attempts, including the first one
+     */
+    pub fn set_max_send_attempts(&mut self, max_attempts: u32) {
+        assert_eq!(env::predecessor_account_id(), self.owner_id, "Unauthorize");
+        self.max_send_attempts = max_attempts;
+    }
+
+    /**
+     * Record the result of sending a message, called back by the contract after
+     * `send_message` of the cross-chain contract. Returns the message id, or none if the
+     * message was not sent
+     * @param sender - account which sent the message
+     * @param to_chain - to chain name
+     * @param content - content of the message
+     * @param session - session of the message
+     * @param attempts - number of times the message failed to be sent before
+     */
+    pub fn on_message_sent(
+        &mut self,
+        sender: AccountId,
+        to_chain: String,
+        content: Content,
+        session: Option<Session>,
+        attempts: u32,
+    ) -> Option<U128> {
+        let id = match env::promise_result(0) {
+            // The id is returned as a number or a string depending on the serializer.
+            PromiseResult::Successful(result) => serde_json::from_slice::<U128>(&result)
+                .or_else(|_| serde_json::from_slice::<u128>(&result).map(U128))
+                .ok(),
+            _ => None,
+        };
+        match id {
+            Some(id) => {
+                events::emit("message_sent", json!({ "id": id, "to_chain": to_chain }));
+                if self.outbound_log_enabled {
+                    let session_type = session.map(|session| session.session_type);
+                    let message = OutboundMessage {
+                        sender,
+                        to_chain,
+                        payload_hash: env::sha256(&content.data.try_to_vec().unwrap()),
+                        contract: content.contract,
+                        action: content.action,
+                        session_type,
+                        timestamp: U64(env::block_timestamp()),
+                        status: if session_type == Some(Session::REQUEST) {
+                            OutboundStatus::Pending
+                        } else {
+                            OutboundStatus::Sent
+                        },
+                    };
+                    self.log_outbound_message(id.0, message);
+                }
+            }
+            None => {
+                let failed_id = self.next_failed_send_id;
+                self.next_failed_send_id += 1;
+                events::emit(
+                    "send_failed",
+                    json!({
+                        "failed_id": U64(failed_id),
+                        "to_chain": to_chain,
+                        "attempts": attempts + 1,
+                    }),
+                );
+                self.failed_send.insert(
+                    &failed_id,
+                    &FailedSend {
+                        sender,
+                        to_chain,
+                        content,
+                        session,
+                        timestamp: U64(env::block_timestamp()),
+                        attempts: attempts + 1,
+                    },
+                );
+            }
+        }
+        id
+    }
+
+    /**
+     * Send a cross-chain message without session. If a route is registered for the chain,
+     * the message is wrapped in a forwarding envelope for each hop and sent to the first
+     * @param to_chain - to chain name
+     * @param content - message content
+     */
+    pub fn call_cross(&mut self, to_chain: String, content: Content) -> Promise {
         self.internal_call_omni_chain(to_chain, content, None)
     }
 
-    pub fn call_cross_with_session(
+    fn route_content(
+        hops: Vec<(String, DstContract)>,
+        to_chain: String,
+        content: Content,
+    ) -> (String, Content) {
+        hops.into_iter().rev().fold(
+            (to_chain, content),
+            |(to_chain, content), (chain, forwarder)| {
+                let content = Content {
+                    contract: forwarder.contract_address,
+                    action: forwarder.action_name,
+                    data: Envelope { to_chain, content }.into_payload(),
+                };
+                (chain, content)
+            },
+        )
+    }
+
+    // The chain and content a message sent to the first hop of a route is meant for.
+    fn final_destination(to_chain: &str, content: &Content) -> (String, Content) {
+        let mut destination = (to_chain.to_string(), content.clone());
+        while let Ok(Some(envelope)) = Envelope::from_payload(&destination.1.data) {
+            destination = (envelope.to_chain, envelope.content);
+        }
+        destination
+    }
+
+    /**
+     * Register the forwarders through which messages without session reach a chain which
+     * is not reachable directly
+     * @param chain_name - to chain name
+     * @param hops - chain name, forwarder contract address and forwarder action name of
+     * each intermediate chain, from the first to the last, or empty to remove the route
+     */
+    pub fn register_dst_route(&mut self, chain_name: String, hops: Vec<(String, String, String)>) {
+        assert_eq!(env::predecessor_account_id(), self.owner_id, "Unauthorize");
+        if hops.is_empty() {
+            self.dst_route.remove(&chain_name);
+            return;
+        }
+        let hops: Vec<(String, DstContract)> = hops
+            .into_iter()
+            .map(|(chain, contract_address, action_name)| {
+                let dst_contract = DstContract {
+                    contract_address: hex::decode(contract_address.strip_prefix("0x").unwrap())
+                        .unwrap(),
+                    action_name: hex::decode(action_name.strip_prefix("0x").unwrap()).unwrap(),
+                };
+                (chain, dst_contract)
+            })
+            .collect();
+        self.dst_route.insert(&chain_name, &hops);
+    }
+
+    /**
+     * Send on the content of a forwarding envelope received from another chain, the
+     * handler of forwarders. Routed messages have no session, so neither has the
+     * forwarded one
+     * @param payload - payload of the received message
+     */
+    pub fn forward_message(&mut self, payload: Payload) -> Promise {
+        let envelope = Envelope::from_payload(&payload)
+            .unwrap_or_else(|err| env::panic_str(&format!("Invalid payload: {}", err)))
+            .expect("Not a forwarding envelope");
+        self.internal_call_omni_chain(envelope.to_chain, envelope.content, None)
+    }
+
+    /**
+     * Send the same payload to the contracts registered for an action on several chains.
+     * The promise resolves to the ids of the sent messages and the chains the message
+     * couldn't be sent to, including those without contract registered for the action
+     * @param chains - to chain names, each at most once
+     * @param action_name - action name used when registering the destination contracts
+     * @param payload - payload of the messages
+     */
+    pub fn call_cross_multi(
+        &mut self,
+        chains: Vec<String>,
+        action_name: String,
+        payload: Payload,
+    ) -> Promise {
+        let mut sent_chains = Vec::new();
+        let mut unregistered = Vec::new();
+        let mut promise: Option<Promise> = None;
+        for chain in chains {
+            let dst_contract = match self.find_dst_contract(&chain, &action_name) {
+                Some(dst_contract) => dst_contract,
+                None => {
+                    unregistered.push(chain);
+                    continue;
+                }
+            };
+            let content = Content {
+                contract: dst_contract.contract_address,
+                action: dst_contract.action_name,
+                data: payload.clone(),
+            };
+            let sent = self.call_cross(chain.clone(), content);
+            sent_chains.push(chain);
+            promise = Some(match promise {
+                Some(promise) => promise.and(sent),
+                None => sent,
+            });
+        }
+        let callback = ext_omni_chain_self::on_multicast_sent(
+            sent_chains,
+            unregistered,
+            env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_MULTICAST_CALLBACK,
+        );
+        // Without any chain registered, the callback alone reports every chain failed.
+        match promise {
+            Some(promise) => promise.then(callback),
+            None => callback,
+        }
+    }
+
+    /**
+     * Collect the results of the messages sent by `call_cross_multi`
+     * @param chains - chains the messages were sent to, in the order of the promises
+     * @param unregistered - chains without contract registered for the action
+     */
+    pub fn on_multicast_sent(
         &self,
+        chains: Vec<String>,
+        unregistered: Vec<String>,
+    ) -> MulticastResult {
+        let mut result = MulticastResult {
+            message_ids: Vec::new(),
+            failed_chains: unregistered,
+        };
+        for (index, chain) in chains.into_iter().enumerate() {
+            let id = match env::promise_result(index as u64) {
+                PromiseResult::Successful(value) => serde_json::from_slice::<Option<U128>>(&value)
+                    .ok()
+                    .flatten(),
+                _ => None,
+            };
+            match id {
+                Some(id) => result.message_ids.push((chain, id)),
+                None => result.failed_chains.push(chain),
+            }
+        }
+        if !result.failed_chains.is_empty() {
+            events::emit(
+                "multicast_failed",
+                json!({ "failed_chains": result.failed_chains }),
+            );
+        }
+        result
+    }
+
+    /**
+     * Send a request to the contracts registered for an action on several chains, and
+     * collect their responses under a correlation id. The method `callback` of this
+     * contract is called with `correlation_id` and `responses` once `quorum` chains
+     * responded successfully, all chains responded, or the timeout passed and
+     * anyone called `complete_gather`. Returns the correlation id
+     * @param chains - to chain names
+     * @param action_name - action name used when registering the destination contracts
+     * @param payload - payload of the requests
+     * @param callback - method receiving the responses, also the action the responses
+     * are sent to, so the destination contracts must be permitted to call it
+     * @param quorum - number of successful responses to wait for, between 1 and the
+     * number of chains, all chains if none
+     * @param timeout - nanoseconds after which the request can be completed with the
+     * responses received so far, so no request stays stored forever
+     */
+    pub fn scatter_request(
+        &mut self,
+        chains: Vec<String>,
+        action_name: String,
+        payload: Payload,
+        callback: String,
+        quorum: Option<u32>,
+        timeout: u64,
+    ) -> u64 {
+        assert!(!chains.is_empty(), "No chain to request");
+        for (index, chain) in chains.iter().enumerate() {
+            assert!(
+                !chains[..index].contains(chain),
+                "Duplicate chain {}",
+                chain
+            );
+        }
+        let quorum = quorum.unwrap_or(chains.len() as u32);
+        assert!(
+            quorum > 0 && quorum as usize <= chains.len(),
+            "Invalid quorum"
+        );
+        assert!(timeout > 0, "Invalid timeout");
+        let deadline = env::block_timestamp()
+            .checked_add(timeout)
+            .expect("Invalid timeout");
+        let correlation_id = self.next_correlation_id;
+        self.next_correlation_id += 1;
+        let promise = chains
+            .iter()
+            .map(|chain| {
+                let dst_contract = self.get_dst_contract(chain, &action_name);
+                let content = Content {
+                    contract: dst_contract.contract_address,
+                    action: dst_contract.action_name,
+                    data: payload.clone(),
+                };
+                self.call_cross_with_session(chain.clone(), content, callback.clone().into_bytes())
+            })
+            .reduce(|promise, next| promise.and(next))
+            .unwrap();
+        promise.then(ext_omni_chain_self::on_scatter_sent(
+            correlation_id,
+            chains.clone(),
+            env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_SCATTER_CALLBACK,
+        ));
+        self.gather.insert(
+            &correlation_id,
+            &Gather {
+                callback,
+                quorum,
+                deadline: U64(deadline),
+                chains,
+                message_ids: Vec::new(),
+                responses: Vec::new(),
+            },
+        );
+        correlation_id
+    }
+
+    /**
+     * Map the ids of the requests sent by `scatter_request` to the correlation id, the
+     * requests which were not sent count as failed responses
+     * @param correlation_id - correlation id of the requests
+     * @param chains - chains the requests were sent to, in the order of the promises
+     */
+    pub fn on_scatter_sent(&mut self, correlation_id: u64, chains: Vec<String>) {
+        let mut gather = match self.gather.get(&correlation_id) {
+            Some(gather) => gather,
+            None => return,
+        };
+        for (index, chain) in chains.into_iter().enumerate() {
+            let id = match env::promise_result(index as u64) {
+                PromiseResult::Successful(value) => serde_json::from_slice::<Option<U128>>(&value)
+                    .ok()
+                    .flatten(),
+                _ => None,
+            };
+            match id {
+                Some(id) => {
+                    self.gather_message
+                        .insert(&(chain.clone(), id.0), &correlation_id);
+                    gather.message_ids.push((chain, id));
+                }
+                None => gather.responses.push(GatherResponse {
+                    chain,
+                    payload: None,
+                    error: Some(SessionError::new(SessionError::SEND_FAILED, "send failed")),
+                }),
+            }
+        }
+        self.update_gather(correlation_id, gather, false);
+    }
+
+    /**
+     * Call the callback of a scatter-gather request after its timeout with the responses
+     * received so far. Callable by anyone
+     * @param correlation_id - correlation id of the request
+     */
+    pub fn complete_gather(&mut self, correlation_id: u64) {
+        let gather = self.gather.get(&correlation_id).expect("request not exist");
+        assert!(
+            env::block_timestamp() > gather.deadline.0,
+            "Request not timed out"
+        );
+        self.update_gather(correlation_id, gather, true);
+    }
+
+    fn receive_gather_response(&mut self, payload: &Payload, context: &Context) -> bool {
+        let error = match context.session.session_type {
+            Session::RESPONSE => None,
+            Session::ERROR => SessionError::from_payload(payload).ok(),
+            _ => return false,
+        };
+        let key = (context.from_chain.clone(), context.session.id.0);
+        let correlation_id = match self.gather_message.get(&key) {
+            Some(correlation_id) => correlation_id,
+            None => return false,
+        };
+        let mut gather = self.gather.get(&correlation_id).unwrap();
+        if !gather.chains.contains(&context.from_chain)
+            || gather
+                .responses
+                .iter()
+                .any(|response| response.chain == context.from_chain)
+        {
+            return false;
+        }
+        self.gather_message.remove(&key);
+        gather.responses.push(GatherResponse {
+            chain: context.from_chain.clone(),
+            payload: error.is_none().then(|| payload.clone()),
+            error,
+        });
+        self.update_gather(correlation_id, gather, false);
+        true
+    }
+
+    fn update_gather(&mut self, correlation_id: u64, gather: Gather, timed_out: bool) {
+        let succeeded = gather
+            .responses
+            .iter()
+            .filter(|response| response.error.is_none())
+            .count() as u32;
+        let completed = timed_out
+            || succeeded >= gather.quorum
+            || gather.responses.len() == gather.chains.len();
+        if !completed {
+            self.gather.insert(&correlation_id, &gather);
+            return;
+        }
+        self.gather.remove(&correlation_id);
+        for (chain, id) in &gather.message_ids {
+            self.gather_message.remove(&(chain.clone(), id.0));
+        }
+        events::emit(
+            "gather_completed",
+            json!({ "correlation_id": U64(correlation_id), "responses": gather.responses.len() }),
+        );
+        let args = json!({
+            "correlation_id": U64(correlation_id),
+            "responses": gather.responses,
+        });
+        Promise::new(env::current_account_id()).function_call(
+            gather.callback,
+            args.to_string().into_bytes(),
+            NO_DEPOSIT,
+            GAS_FOR_GATHER_CALLBACK,
+        );
+    }
+
+    pub fn call_cross_with_session(
+        &mut self,
         to_chain: String,
         content: Content,
         callback: Vec<u8>,
@@ -78,7 +1027,7 @@ impl OmniChain {
             content,
             Some(Session {
                 id: U128(0),
-                session_type: 2,
+                session_type: Session::REQUEST,
                 callback: Some(callback),
                 commitment: None,
                 answer: None,
@@ -86,103 +1035,1142 @@ impl OmniChain {
         )
     }
 
-    pub fn send_response_message(&self, to_chain: String, content: Content, id: U128) {
-        self.internal_call_omni_chain(
-            to_chain,
-            content,
-            Some(Session {
-                id,
-                session_type: 1,
-                callback: None,
-                commitment: None,
-                answer: None,
-            }),
+    /**
+     * Send a payload too large for one message as fragments sharing a transfer id, the
+     * receiver reassembles them and calls the handler once with the full payload
+     * @param to_chain - to chain name
+     * @param content - message content, `content.data` is split
+     * @param chunk_size - max length of the payload data carried by each fragment in bytes,
+     * large enough for at most `MAX_TRANSFER_FRAGMENTS` fragments
+     * @param callback - callback of the session, or none to send without session
+     */
+    pub fn call_cross_chunked(
+        &mut self,
+        to_chain: String,
+        content: Content,
+        chunk_size: usize,
+        callback: Option<Vec<u8>>,
+    ) -> Promise {
+        // The random seed is the same for the whole transaction, the nonce tells apart
+        // transfers of the same content.
+        let nonce = self.next_transfer_nonce;
+        self.next_transfer_nonce += 1;
+        let seed = [
+            env::random_seed(),
+            nonce.to_le_bytes().to_vec(),
+            content.try_to_vec().unwrap(),
+        ]
+        .concat();
+        let transfer_id: [u8; 32] = env::sha256(&seed).try_into().unwrap();
+        let fragments = Fragment::split::<NearHasher>(&content.data, transfer_id, chunk_size);
+        assert!(
+            fragments.len() as u32 <= MAX_TRANSFER_FRAGMENTS,
+            "Transfer of more than {} fragments",
+            MAX_TRANSFER_FRAGMENTS
         );
+        fragments
+            .into_iter()
+            .map(|fragment| {
+                let content = Content {
+                    contract: content.contract.clone(),
+                    action: content.action.clone(),
+                    data: fragment.into_payload(),
+                };
+                match &callback {
+                    Some(callback) => {
+                        self.call_cross_with_session(to_chain.clone(), content, callback.clone())
+                    }
+                    None => self.call_cross(to_chain.clone(), content),
+                }
+            })
+            .reduce(|promise, next| promise.and(next))
+            .unwrap()
     }
 
-    pub fn register_dst_contract(
+    /**
+     * Send a message on the ordered channel to the destination contract, the receiver
+     * calls the handler of messages of the channel in the order they were sent
+     * @param to_chain - to chain name
+     * @param content - message content, the sequence number is added to `content.data`,
+     * whose item names must not start with `__`
+     * @param callback - callback of the session, or none to send without session
+     */
+    pub fn call_cross_ordered(
         &mut self,
-        chain_name: String,
-        action_name: String,
-        contract_address: String,
-        contract_action_name: String,
-    ) {
-        assert_eq!(env::predecessor_account_id(), self.owner_id, "Unauthorize");
-        let contract_address = hex::decode(contract_address.strip_prefix("0x").unwrap()).unwrap();
-        let contract_action_name =
-            hex::decode(contract_action_name.strip_prefix("0x").unwrap()).unwrap();
-        match self.destination_contract.get(&chain_name) {
-            Some(mut map) => {
-                // if !map.contains_key(&action_name) {
-                //     map.insert(
-                //         action_name,
-                //         DstContract {
-                //             contract_address,
-                //             action_name: contract_action_name,
-                //         },
-                //     );
-                // } else {
-                //     env::panic_str("Already contains");
-                // }
-                map.insert(
-                    action_name,
-                    DstContract {
-                        contract_address,
-                        action_name: contract_action_name,
-                    },
-                );
-                self.destination_contract.insert(&chain_name, &map);
-            }
-            _ => {
-                let mut ms = HashMap::new();
-                ms.insert(
-                    action_name,
-                    DstContract {
-                        contract_address,
-                        action_name: contract_action_name,
-                    },
-                );
-                self.destination_contract.insert(&chain_name, &ms);
-            }
+        to_chain: String,
+        mut content: Content,
+        callback: Option<Vec<u8>>,
+    ) -> Promise {
+        if let Err(err) = content.data.check_reserved_names() {
+            env::panic_str(&format!("Invalid payload: {}", err));
+        }
+        let key = (to_chain.clone(), content.contract.clone());
+        let sequence = self.outbound_sequence.get(&key).unwrap_or(0);
+        self.outbound_sequence.insert(&key, &(sequence + 1));
+        content
+            .data
+            .set_item(SEQUENCE_ITEM.to_string(), Value::Uint64(sequence));
+        match callback {
+            Some(callback) => self.call_cross_with_session(to_chain, content, callback),
+            None => self.call_cross(to_chain, content),
         }
     }
 
-    ///////////////////////////////////////////////
-    ///    Receive messages from other chains   ///
-    ///////////////////////////////////////////////
+    /**
+     * Get the sequence number of the next message sent on an ordered channel
+     * @param chain_name - to chain name
+     * @param contract_address - destination contract address
+     */
+    pub fn get_next_outbound_sequence(&self, chain_name: String, contract_address: String) -> u64 {
+        let contract_address = hex::decode(contract_address.strip_prefix("0x").unwrap()).unwrap();
+        self.outbound_sequence
+            .get(&(chain_name, contract_address))
+            .unwrap_or(0)
+    }
+
+    fn log_outbound_message(&mut self, id: u128, message: OutboundMessage) {
+        let chain_position = self
+            .outbound_chain_count
+            .get(&message.to_chain)
+            .unwrap_or(0);
+        self.outbound_by_chain
+            .insert(&(message.to_chain.clone(), chain_position), &id);
+        self.outbound_chain_count
+            .insert(&message.to_chain, &(chain_position + 1));
+        let key = (message.to_chain.clone(), id);
+        let sender_position = self.outbound_sender_count.get(&message.sender).unwrap_or(0);
+        self.outbound_by_sender
+            .insert(&(message.sender.clone(), sender_position), &key);
+        self.outbound_sender_count
+            .insert(&message.sender, &(sender_position + 1));
+        self.outbound_message.insert(&key, &message);
+    }
 
     /**
-     * Authorize contracts of other chains to call the action of this contract
-     * @param chain_name - from chain name
-     * @param sender - sender of cross chain message
-     * @param action_name - action name which allowed to be invoked
+     * Configure the log of sent messages, disabled by default
+     * @param enabled - whether sent messages are recorded
+     * @param response_timeout - nanoseconds after which a request without response is
+     * reported as timed out, or none to wait forever
      */
-    pub fn register_permitted_contract(
-        &mut self,
-        chain_name: String,
-        sender: String,
-        action_name: String,
-    ) {
-        // assert_eq!(self.owner_id, env::predecessor_account_id(), "Unauthorize");
-        let sender = hex::decode(sender.strip_prefix("0x").unwrap()).unwrap();
-        let key = (chain_name, sender);
-        let mut actions = Vec::new();
-        if let Some(acts) = self.permitted_contract.get(&key) {
-            assert!(!actions.contains(&action_name), "Already exist");
-            actions.extend(acts.into_iter());
-        }
-        actions.push(action_name);
-        self.permitted_contract.insert(&key, &actions);
+    pub fn set_outbound_log(&mut self, enabled: bool, response_timeout: Option<U64>) {
+        assert_eq!(env::predecessor_account_id(), self.owner_id, "Unauthorize");
+        self.outbound_log_enabled = enabled;
+        self.response_timeout = response_timeout.map(|timeout| timeout.0);
     }
 
-    pub fn assert_register_permitted_contract(
+    /**
+     * Limit the messages each contract of a chain may send to this contract
+     * @param chain_name - from chain name
+     * @param limit - max messages per sender in each window, or none to remove the limit
+     */
+    pub fn set_inbound_rate_limit(&mut self, chain_name: String, limit: Option<RateLimit>) {
+        assert_eq!(env::predecessor_account_id(), self.owner_id, "Unauthorize");
+        match limit {
+            Some(limit) => self.inbound_rate_limit.insert(&chain_name, &limit),
+            None => self.inbound_rate_limit.remove(&chain_name),
+        };
+    }
+
+    /**
+     * Limit the messages each account may send to a chain through this contract, the
+     * contract itself is not limited
+     * @param chain_name - to chain name
+     * @param limit - max messages per account in each window, or none to remove the limit
+     */
+    pub fn set_outbound_rate_limit(&mut self, chain_name: String, limit: Option<RateLimit>) {
+        assert_eq!(env::predecessor_account_id(), self.owner_id, "Unauthorize");
+        match limit {
+            Some(limit) => self.outbound_rate_limit.insert(&chain_name, &limit),
+            None => self.outbound_rate_limit.remove(&chain_name),
+        };
+    }
+
+    fn assert_outbound_rate(&mut self, sender: &AccountId, to_chain: &String) {
+        if *sender == env::current_account_id() {
+            return;
+        }
+        if let Some(limit) = self.outbound_rate_limit.get(to_chain) {
+            let key = (sender.clone(), to_chain.clone());
+            let usage = Self::count_rate(self.outbound_rate_usage.get(&key), &limit);
+            assert!(
+                usage.is_some(),
+                "Rate limit of {} messages to {} exceeded for {}",
+                limit.max_messages,
+                to_chain,
+                sender
+            );
+            self.outbound_rate_usage.insert(&key, &usage.unwrap());
+        }
+    }
+
+    fn assert_inbound_rate(&mut self, context: &Context) {
+        if let Some(limit) = self.inbound_rate_limit.get(&context.from_chain) {
+            let key = (context.from_chain.clone(), context.sender.clone());
+            let usage = Self::count_rate(self.inbound_rate_usage.get(&key), &limit);
+            assert!(
+                usage.is_some(),
+                "Rate limit of {} messages from {} exceeded for 0x{}",
+                limit.max_messages,
+                context.from_chain,
+                hex::encode(&context.sender)
+            );
+            self.inbound_rate_usage.insert(&key, &usage.unwrap());
+        }
+    }
+
+    /// Counts a message in the current window, none if the limit is reached. An expired
+    /// window is replaced by one starting now.
+    fn count_rate(usage: Option<RateUsage>, limit: &RateLimit) -> Option<RateUsage> {
+        let now = env::block_timestamp();
+        let mut usage = usage
+            .filter(|usage| now < usage.window_start.saturating_add(limit.window.0))
+            .unwrap_or(RateUsage {
+                window_start: now,
+                count: 0,
+            });
+        if usage.count >= limit.max_messages {
+            return None;
+        }
+        usage.count += 1;
+        Some(usage)
+    }
+
+    /**
+     * Get a sent message from the log
+     * @param chain_name - to chain name
+     * @param id - message id returned by the cross-chain contract
+     */
+    pub fn get_outbound_message(&self, chain_name: String, id: U128) -> Option<OutboundMessage> {
+        self.outbound_message
+            .get(&(chain_name, id.0))
+            .map(|message| self.with_timeout(message))
+    }
+
+    /**
+     * List sent messages from the log, read from the index of the chain or the sender.
+     * With both, the messages of the sender are scanned for the ones sent to the chain
+     * @param chain_name - only messages sent to this chain if given
+     * @param sender - only messages sent by this account if given
+     * @param from_index - number of matching messages to skip
+     * @param limit - max number of messages returned
+     */
+    pub fn get_outbound_messages(
+        &self,
+        chain_name: Option<String>,
+        sender: Option<AccountId>,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<(U128, OutboundMessage)> {
+        match (chain_name, sender) {
+            (chain_name, Some(sender)) => {
+                let count = self.outbound_sender_count.get(&sender).unwrap_or(0);
+                (0..count)
+                    .filter_map(|position| self.outbound_by_sender.get(&(sender.clone(), position)))
+                    .filter_map(|key| Some((key.1, self.outbound_message.get(&key)?)))
+                    .filter(|(_, message)| {
+                        chain_name.iter().all(|chain| &message.to_chain == chain)
+                    })
+                    .skip(from_index as usize)
+                    .take(limit as usize)
+                    .map(|(id, message)| (U128(id), self.with_timeout(message)))
+                    .collect()
+            }
+            (Some(chain_name), None) => {
+                let count = self.outbound_chain_count.get(&chain_name).unwrap_or(0);
+                (from_index..count)
+                    .take(limit as usize)
+                    .filter_map(|position| {
+                        self.outbound_by_chain.get(&(chain_name.clone(), position))
+                    })
+                    .filter_map(|id| {
+                        Some((id, self.outbound_message.get(&(chain_name.clone(), id))?))
+                    })
+                    .map(|(id, message)| (U128(id), self.with_timeout(message)))
+                    .collect()
+            }
+            (None, None) => self
+                .outbound_message
+                .iter()
+                .skip(from_index as usize)
+                .take(limit as usize)
+                .map(|((_, id), message)| (U128(id), self.with_timeout(message)))
+                .collect(),
+        }
+    }
+
+    fn with_timeout(&self, mut message: OutboundMessage) -> OutboundMessage {
+        if let (OutboundStatus::Pending, Some(timeout)) = (message.status, self.response_timeout) {
+            if env::block_timestamp() > message.timestamp.0 + timeout {
+                message.status = OutboundStatus::TimedOut;
+            }
+        }
+        message
+    }
+
+    pub fn send_response_message(
+        &mut self,
+        to_chain: String,
+        content: Content,
+        id: U128,
+    ) -> Promise {
+        self.internal_call_omni_chain(
+            to_chain,
+            content,
+            Some(Session {
+                id,
+                session_type: Session::RESPONSE,
+                callback: None,
+                commitment: None,
+                answer: None,
+            }),
+        )
+    }
+
+    /**
+     * Respond a request with an error, the requester's `on_error` hook is called instead
+     * of the callback. Nothing is sent if the message isn't a request
+     * @param context - context of the request
+     * @param error - why the request failed
+     */
+    pub fn send_error_response(&mut self, context: &Context, error: SessionError) {
+        if context.session.session_type != Session::REQUEST {
+            return;
+        }
+        if let Some(callback) = context.session.callback.clone() {
+            let content = Content {
+                contract: context.sender.clone(),
+                action: callback,
+                data: error.into_payload(),
+            };
+            self.internal_call_omni_chain(
+                context.from_chain.clone(),
+                content,
+                Some(Session {
+                    id: context.session.id,
+                    session_type: Session::ERROR,
+                    callback: None,
+                    commitment: None,
+                    answer: None,
+                }),
+            );
+        }
+    }
+
+    pub fn register_dst_contract(
+        &mut self,
+        chain_name: String,
+        action_name: String,
+        contract_address: String,
+        contract_action_name: String,
+    ) {
+        assert_eq!(env::predecessor_account_id(), self.owner_id, "Unauthorize");
+        let contract_address = hex::decode(contract_address.strip_prefix("0x").unwrap()).unwrap();
+        let contract_action_name =
+            hex::decode(contract_action_name.strip_prefix("0x").unwrap()).unwrap();
+        let key = (chain_name, action_name);
+        self.destination_contract.insert(
+            &key,
+            &DstContract {
+                contract_address,
+                action_name: contract_action_name,
+            },
+        );
+        self.dst_contract_index.insert(&key);
+    }
+
+    /**
+     * Get the contracts registered with `register_dst_contract`
+     * @param from_index - index of the first contract
+     * @param limit - max number of contracts returned
+     */
+    pub fn get_registered_dst_contracts(
+        &self,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<(DstKey, DstContract)> {
+        self.dst_contract_index
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .filter_map(|key| self.destination_contract.get(&key).map(|dst| (key, dst)))
+            .collect()
+    }
+
+    /**
+     * Limit the size of payloads sent to a chain, messages over it are rejected before
+     * they are sent to the cross-chain contract
+     * @param chain_name - to chain name
+     * @param max_size - max length of the encoded payload in bytes, or none to remove the limit
+     */
+    pub fn set_max_payload_size(&mut self, chain_name: String, max_size: Option<u32>) {
+        assert_eq!(env::predecessor_account_id(), self.owner_id, "Unauthorize");
+        match max_size {
+            Some(max_size) => self.max_payload_size.insert(&chain_name, &max_size),
+            None => self.max_payload_size.remove(&chain_name),
+        };
+    }
+
+    /**
+     * Set how long partial chunked transfers are kept, after which their fragments can be
+     * discarded with `clear_expired_transfers`
+     * @param timeout - nanoseconds after the first fragment of a transfer
+     */
+    pub fn set_chunk_transfer_timeout(&mut self, timeout: U64) {
+        assert_eq!(env::predecessor_account_id(), self.owner_id, "Unauthorize");
+        self.chunk_transfer_timeout = timeout.0;
+    }
+
+    /**
+     * Discard partial chunked transfers older than the transfer timeout with their fragments,
+     * returns the number of transfers discarded
+     * @param limit - max number of transfers discarded
+     */
+    pub fn clear_expired_transfers(&mut self, limit: u64) -> u64 {
+        let expired: Vec<(TransferKey, ChunkTransfer)> = self
+            .chunk_transfer
+            .iter()
+            .filter(|(_, transfer)| self.is_transfer_expired(transfer))
+            .take(limit as usize)
+            .collect();
+        for (key, transfer) in expired.iter() {
+            self.remove_transfer(key, transfer);
+        }
+        expired.len() as u64
+    }
+
+    fn is_transfer_expired(&self, transfer: &ChunkTransfer) -> bool {
+        env::block_timestamp().saturating_sub(transfer.started_at) > self.chunk_transfer_timeout
+    }
+
+    fn remove_transfer(&mut self, key: &TransferKey, transfer: &ChunkTransfer) {
+        self.chunk_transfer.remove(key);
+        for index in transfer.received.iter() {
+            self.chunk_fragment.remove(&(key.clone(), *index));
+        }
+    }
+
+    pub fn assert_payload_size(&self, to_chain: &String, content: &Content) {
+        if let Some(max_size) = self.max_payload_size.get(to_chain) {
+            let size = content.data.encoded_size();
+            assert!(
+                size <= max_size as usize,
+                "Payload of {} bytes exceeds the limit of {} bytes for {}",
+                size,
+                max_size,
+                to_chain
+            );
+        }
+    }
+
+    /**
+     * Get the contract registered to handle an action on another chain
+     * @param chain_name - to chain name
+     * @param action_name - action name used when registering the destination contract
+     */
+    pub fn get_dst_contract(&self, chain_name: &str, action_name: &str) -> DstContract {
+        let key = (chain_name.to_string(), action_name.to_string());
+        if let Some(candidates) = self.dst_candidates.get(&key) {
+            return Self::select_candidate(candidates).expect("no healthy contract");
+        }
+        self.destination_contract
+            .get(&key)
+            .expect("contract not register")
+    }
+
+    fn find_dst_contract(&self, chain_name: &str, action_name: &str) -> Option<DstContract> {
+        let key = (chain_name.to_string(), action_name.to_string());
+        match self.dst_candidates.get(&key) {
+            Some(candidates) => Self::select_candidate(candidates),
+            None => self.destination_contract.get(&key),
+        }
+    }
+
+    fn select_candidate(candidates: Vec<DstCandidate>) -> Option<DstContract> {
+        candidates
+            .into_iter()
+            .find(|candidate| candidate.healthy)
+            .map(|candidate| candidate.contract)
+    }
+
+    /**
+     * Add a contract handling an action on another chain, or update its priority. Once
+     * an action has such contracts, messages go to the healthy one with the lowest
+     * priority instead of the contract set by `register_dst_contract`
+     * @param chain_name - to chain name
+     * @param action_name - action name used when sending the message
+     * @param contract_address - destination contract address
+     * @param contract_action_name - action name of the destination contract
+     * @param priority - contracts with lower priority are chosen first
+     */
+    pub fn add_dst_contract(
+        &mut self,
+        chain_name: String,
+        action_name: String,
+        contract_address: String,
+        contract_action_name: String,
+        priority: u32,
+    ) {
+        assert_eq!(env::predecessor_account_id(), self.owner_id, "Unauthorize");
+        let contract = DstContract {
+            contract_address: hex::decode(contract_address.strip_prefix("0x").unwrap()).unwrap(),
+            action_name: hex::decode(contract_action_name.strip_prefix("0x").unwrap()).unwrap(),
+        };
+        let key = (chain_name, action_name);
+        let mut candidates = self.dst_candidates.get(&key).unwrap_or_default();
+        candidates
+            .retain(|candidate| candidate.contract.contract_address != contract.contract_address);
+        candidates.push(DstCandidate {
+            contract,
+            priority,
+            healthy: true,
+        });
+        candidates.sort_by_key(|candidate| candidate.priority);
+        self.dst_candidates.insert(&key, &candidates);
+    }
+
+    /**
+     * Remove a contract added by `add_dst_contract`
+     * @param chain_name - to chain name
+     * @param action_name - action name used when sending the message
+     * @param contract_address - destination contract address
+     */
+    pub fn remove_dst_contract(
+        &mut self,
+        chain_name: String,
+        action_name: String,
+        contract_address: String,
+    ) {
+        assert_eq!(env::predecessor_account_id(), self.owner_id, "Unauthorize");
+        let contract_address = hex::decode(contract_address.strip_prefix("0x").unwrap()).unwrap();
+        let key = (chain_name, action_name);
+        let mut candidates = self.dst_candidates.get(&key).unwrap_or_default();
+        candidates.retain(|candidate| candidate.contract.contract_address != contract_address);
+        if candidates.is_empty() {
+            self.dst_candidates.remove(&key);
+        } else {
+            self.dst_candidates.insert(&key, &candidates);
+        }
+    }
+
+    /**
+     * Mark a contract added by `add_dst_contract` as healthy or not, messages fail over
+     * to the next contract while it's unhealthy
+     * @param chain_name - to chain name
+     * @param action_name - action name used when sending the message
+     * @param contract_address - destination contract address
+     * @param healthy - whether the contract can receive messages
+     */
+    pub fn set_dst_contract_health(
+        &mut self,
+        chain_name: String,
+        action_name: String,
+        contract_address: String,
+        healthy: bool,
+    ) {
+        assert_eq!(env::predecessor_account_id(), self.owner_id, "Unauthorize");
+        let contract_address = hex::decode(contract_address.strip_prefix("0x").unwrap()).unwrap();
+        let key = (chain_name, action_name);
+        let mut candidates = self
+            .dst_candidates
+            .get(&key)
+            .expect("contract not register");
+        let candidate = candidates
+            .iter_mut()
+            .find(|candidate| candidate.contract.contract_address == contract_address)
+            .expect("contract not register");
+        candidate.healthy = healthy;
+        self.dst_candidates.insert(&key, &candidates);
+        events::emit(
+            "dst_contract_health",
+            json!({ "chain_name": key.0, "action_name": key.1, "healthy": healthy }),
+        );
+    }
+
+    ///////////////////////////////////////////////
+    ///    Receive messages from other chains   ///
+    ///////////////////////////////////////////////
+
+    /**
+     * Authorize contracts of other chains to call the action of this contract
+     * @param chain_name - from chain name
+     * @param sender - sender of cross chain message
+     * @param action_name - action name which allowed to be invoked
+     * @param expires_at - block timestamp from which the permission is expired, or none
+     * to never expire
+     * @param max_calls - number of messages the sender may send to the action, or none
+     * for no limit. A chunked transfer counts as one message. Registering again resets
+     * the limits
+     */
+    pub fn register_permitted_contract(
+        &mut self,
+        chain_name: String,
+        sender: String,
+        action_name: String,
+        expires_at: Option<U64>,
+        max_calls: Option<u64>,
+    ) {
+        assert_eq!(self.owner_id, env::predecessor_account_id(), "Unauthorize");
+        let sender = hex::decode(sender.strip_prefix("0x").unwrap()).unwrap();
+        let key = (chain_name, sender, action_name);
+        self.permitted_contract.insert(&key);
+        self.permitted_contract_index.insert(&key);
+        if expires_at.is_none() && max_calls.is_none() {
+            self.permission_limit.remove(&key);
+        } else {
+            let limit = PermissionLimit {
+                expires_at,
+                remaining_calls: max_calls,
+            };
+            self.permission_limit.insert(&key, &limit);
+        }
+    }
+
+    /**
+     * Get the permissions registered with `register_permitted_contract`
+     * @param from_index - index of the first permission
+     * @param limit - max number of permissions returned
+     */
+    pub fn get_permitted_contracts(&self, from_index: u64, limit: u64) -> Vec<PermissionKey> {
+        self.permitted_contract_index
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+
+    /**
+     * Add a rule allowing or denying the senders and actions it matches, e.g. any sender
+     * of a chain to call an action, or a sender to call every action. Returns the id of
+     * the rule
+     * @param chain_name - from chain name, or none for any chain
+     * @param sender - sender of cross chain message, or none for any sender
+     * @param action_name - action name, or none for any action
+     * @param allow - whether matching messages are allowed or denied
+     */
+    pub fn add_permission_rule(
+        &mut self,
+        chain_name: Option<String>,
+        sender: Option<String>,
+        action_name: Option<String>,
+        allow: bool,
+    ) -> u64 {
+        assert_eq!(env::predecessor_account_id(), self.owner_id, "Unauthorize");
+        let rule = PermissionRule {
+            id: U64(self.next_rule_id),
+            chain_name,
+            sender: sender.map(|sender| hex::decode(sender.strip_prefix("0x").unwrap()).unwrap()),
+            action_name,
+            allow,
+        };
+        assert!(
+            !self
+                .permission_rules
+                .iter()
+                .any(|existing| existing.same_fields(&rule)),
+            "Already exist"
+        );
+        self.next_rule_id += 1;
+        self.permission_rules.push(rule);
+        self.next_rule_id - 1
+    }
+
+    /**
+     * Remove a rule added by `add_permission_rule`
+     * @param id - id of the rule returned by `add_permission_rule`
+     */
+    pub fn remove_permission_rule(&mut self, id: u64) {
+        assert_eq!(env::predecessor_account_id(), self.owner_id, "Unauthorize");
+        let index = self
+            .permission_rules
+            .iter()
+            .position(|rule| rule.id.0 == id)
+            .expect("rule not exist");
+        self.permission_rules.remove(index);
+    }
+
+    /**
+     * Get the limits left of the permission of a contract of another chain to call an
+     * action, none if the permission has no limits
+     * @param chain_name - from chain name
+     * @param sender - sender of cross chain message
+     * @param action_name - action name which allowed to be invoked
+     */
+    pub fn get_permission_allowance(
         &self,
+        chain_name: String,
+        sender: String,
+        action_name: String,
+    ) -> Option<PermissionLimit> {
+        let sender = hex::decode(sender.strip_prefix("0x").unwrap()).unwrap();
+        self.permission_limit
+            .get(&(chain_name, sender, action_name))
+    }
+
+    /**
+     * Declare the payload expected by an action of this contract, inbound payloads of the
+     * action are validated against it
+     * @param action_name - action name
+     * @param schema - items of the payload, declaring the contents of compound items
+     */
+    pub fn register_action_schema(&mut self, action_name: String, schema: PayloadSchema) {
+        assert_eq!(env::predecessor_account_id(), self.owner_id, "Unauthorize");
+        if let Err(err) = schema.check() {
+            env::panic_str(&format!("Invalid schema: {}", err));
+        }
+        self.action_schema.insert(&action_name, &schema);
+    }
+
+    pub fn remove_action_schema(&mut self, action_name: String) {
+        assert_eq!(env::predecessor_account_id(), self.owner_id, "Unauthorize");
+        self.action_schema.remove(&action_name);
+    }
+
+    /**
+     * Check a message is delivered by the cross-chain contract, the sender is permitted
+     * to call the action, and the payload matches the schema of the action if declared
+     * @param payload - payload of the received message
+     * @param context - context of the received message
+     */
+    pub fn assert_inbound_message(&mut self, payload: &Payload, context: &Context) {
+        self.assert_inbound_sender(context, true);
+        self.assert_payload_schema(payload, &context.action);
+    }
+
+    /**
+     * Check a message like `assert_inbound_message`, buffering the fragments of chunked
+     * transfers and the messages of ordered channels received ahead of their turn. Returns
+     * the messages to pass to the handlers in order: none while fragments or earlier
+     * messages are missing, or the message followed by the buffered ones it unblocked
+     * @param payload - payload of the received message
+     * @param context - context of the received message
+     */
+    pub fn accept_inbound_message(
+        &mut self,
+        payload: Payload,
+        context: Context,
+    ) -> Vec<(Payload, Context)> {
+        let fragment = Fragment::from_payload(&payload)
+            .unwrap_or_else(|err| env::panic_str(&format!("Invalid payload: {}", err)));
+        self.assert_inbound_sender(&context, fragment.is_none());
+        let mut payload = match fragment {
+            None => payload,
+            Some(fragment) => match self.receive_fragment(&context, fragment) {
+                // The reassembled message counts as one call of the permission.
+                Some(payload) => {
+                    self.check_permission(
+                        &context.from_chain,
+                        &context.sender,
+                        &context.action,
+                        true,
+                    );
+                    payload
+                }
+                None => return Vec::new(),
+            },
+        };
+        if let Err(err) = payload.check_unique_names() {
+            env::panic_str(&format!("Invalid payload: {}", err));
+        }
+        let sequence = payload.remove_item(SEQUENCE_ITEM);
+        self.update_outbound_status(&context);
+        if context.session.session_type == Session::ERROR {
+            if let Err(err) = SessionError::from_payload(&payload) {
+                env::panic_str(&format!("Invalid payload: {}", err));
+            }
+        } else {
+            self.assert_payload_schema(&payload, &context.action);
+        }
+        if self.receive_gather_response(&payload, &context) {
+            return Vec::new();
+        }
+        match sequence {
+            Some(sequence) => {
+                let sequence = u64::try_from(sequence)
+                    .unwrap_or_else(|err| env::panic_str(&format!("Invalid payload: {}", err)));
+                self.receive_in_order(sequence, payload, context)
+            }
+            None => vec![(payload, context)],
+        }
+    }
+
+    fn update_outbound_status(&mut self, context: &Context) {
+        let status = match context.session.session_type {
+            Session::RESPONSE => OutboundStatus::Responded,
+            Session::ERROR => OutboundStatus::Failed,
+            _ => return,
+        };
+        let key = (context.from_chain.clone(), context.session.id.0);
+        if let Some(mut message) = self.outbound_message.get(&key) {
+            if message.contract == context.sender {
+                message.status = status;
+                self.outbound_message.insert(&key, &message);
+            }
+        }
+    }
+
+    /**
+     * Get the sequence number of the next message expected on an ordered channel
+     * @param chain_name - from chain name
+     * @param sender - sender of cross chain message
+     */
+    pub fn get_next_inbound_sequence(&self, chain_name: String, sender: String) -> u64 {
+        let sender = hex::decode(sender.strip_prefix("0x").unwrap()).unwrap();
+        self.inbound_sequence
+            .get(&(chain_name, sender))
+            .unwrap_or(0)
+    }
+
+    /**
+     * Give up on the messages of an ordered channel which won't arrive, e.g. discarded by
+     * the sender, so the channel goes on from a later sequence number. Messages buffered
+     * before it are dropped, the ones buffered from it on are returned to be dispatched
+     * @param chain_name - from chain name
+     * @param sender - sender of cross chain message
+     * @param sequence - sequence number of the next message expected
+     */
+    pub fn skip_inbound_sequence(
+        &mut self,
+        chain_name: String,
+        sender: String,
+        sequence: u64,
+    ) -> Vec<(Payload, Context)> {
+        assert_eq!(env::predecessor_account_id(), self.owner_id, "Unauthorize");
+        let sender = hex::decode(sender.strip_prefix("0x").unwrap()).unwrap();
+        let key = (chain_name, sender);
+        let skipped = self.inbound_sequence.get(&key).unwrap_or(0);
+        assert!(sequence > skipped, "Sequence {} already reached", sequence);
+        let mut buffered = self.sequence_buffered.get(&key).unwrap_or_default();
+        buffered.retain(|&dropped| {
+            if dropped < sequence {
+                self.sequence_buffer.remove(&(key.clone(), dropped));
+            }
+            dropped >= sequence
+        });
+        events::emit(
+            "sequence_skipped",
+            json!({ "from_chain": key.0, "from": U64(skipped), "to": U64(sequence) }),
+        );
+        self.take_buffered(key, sequence, buffered, Vec::new())
+    }
+
+    /**
+     * Run the handler of each accepted message in its own receipt, so a failing handler
+     * doesn't revert the checks and buffers of `accept_inbound_message`, and check the
+     * result with `on_message_dispatched`. In dead-letter mode the messages are stored
+     * first, and kept as dead letters if their handler fails
+     * @param messages - messages returned by `accept_inbound_message`
+     */
+    pub fn dispatch_inbound_messages(&mut self, messages: Vec<(Payload, Context)>) {
+        if messages.is_empty() {
+            return;
+        }
+        let gas = Self::dispatch_gas(messages.len() as u64);
+        for (payload, context) in messages {
+            let inbound_id = if self.dead_letter_enabled {
+                let inbound_id = self.next_inbound_id;
+                self.next_inbound_id += 1;
+                self.inbound_message
+                    .insert(&inbound_id, &(payload.clone(), context.clone()));
+                Some(inbound_id)
+            } else {
+                None
+            };
+            Self::dispatch_message(payload, context, inbound_id, gas);
+        }
+    }
+
+    fn dispatch_gas(count: u64) -> Gas {
+        let remaining = env::prepaid_gas().0 - env::used_gas().0;
+        let reserved = GAS_FOR_ACCEPT_MESSAGE.0 + GAS_FOR_DISPATCH_CALLBACK.0 * count;
+        assert!(remaining > reserved, "Not enough gas to dispatch messages");
+        Gas((remaining - reserved) / count)
+    }
+
+    fn dispatch_message(payload: Payload, context: Context, inbound_id: Option<u64>, gas: Gas) {
+        let args = json!({ "payload": payload, "context": context });
+        let callback_args = json!({ "context": context, "inbound_id": inbound_id });
+        Promise::new(env::current_account_id())
+            .function_call(
+                DISPATCH_METHOD.to_string(),
+                args.to_string().into_bytes(),
+                NO_DEPOSIT,
+                gas,
+            )
+            .then(Promise::new(env::current_account_id()).function_call(
+                DISPATCH_CALLBACK_METHOD.to_string(),
+                callback_args.to_string().into_bytes(),
+                NO_DEPOSIT,
+                GAS_FOR_DISPATCH_CALLBACK,
+            ));
+    }
+
+    /**
+     * Check the result of the handler of a message. If it failed, the message is kept as
+     * a dead letter in dead-letter mode, otherwise an error response is sent to the
+     * requester
+     * @param context - context of the message
+     * @param inbound_id - id of the stored message in dead-letter mode
+     */
+    pub fn on_message_dispatched(&mut self, context: Context, inbound_id: Option<u64>) {
+        let failed = matches!(env::promise_result(0), PromiseResult::Failed);
+        if let Some(inbound_id) = inbound_id {
+            let (payload, context) = self
+                .inbound_message
+                .remove(&inbound_id)
+                .expect("inbound message not exist");
+            if failed {
+                events::emit(
+                    "dead_letter",
+                    json!({ "id": U64(inbound_id), "from_chain": context.from_chain }),
+                );
+                self.dead_letter.insert(
+                    &inbound_id,
+                    &DeadLetter {
+                        payload,
+                        context,
+                        reason: "handler failed".to_string(),
+                        timestamp: U64(env::block_timestamp()),
+                    },
+                );
+            }
+        } else if failed {
+            env::log_str(&format!(
+                "Failed to process message {} from {}",
+                context.id.0, context.from_chain
+            ));
+            self.send_error_response(
+                &context,
+                SessionError::new(SessionError::HANDLER_FAILED, "handler failed"),
+            );
+        }
+    }
+
+    /**
+     * Store inbound messages before dispatching them, and keep those whose handler fails
+     * as dead letters instead of responding with an error
+     * @param enabled - whether dead-letter mode is on
+     */
+    pub fn set_dead_letter_mode(&mut self, enabled: bool) {
+        assert_eq!(env::predecessor_account_id(), self.owner_id, "Unauthorize");
+        self.dead_letter_enabled = enabled;
+    }
+
+    /**
+     * Dispatch a dead letter again, it's kept again if the handler fails anew
+     * @param id - id of the dead letter
+     */
+    pub fn replay_dead_letter(&mut self, id: u64) {
+        assert_eq!(env::predecessor_account_id(), self.owner_id, "Unauthorize");
+        let dead_letter = self.dead_letter.remove(&id).expect("dead letter not exist");
+        self.inbound_message.insert(
+            &id,
+            &(dead_letter.payload.clone(), dead_letter.context.clone()),
+        );
+        Self::dispatch_message(
+            dead_letter.payload,
+            dead_letter.context,
+            Some(id),
+            Self::dispatch_gas(1),
+        );
+    }
+
+    /**
+     * Drop a dead letter, an error response is sent to the requester
+     * @param id - id of the dead letter
+     */
+    pub fn discard_dead_letter(&mut self, id: u64) {
+        assert_eq!(env::predecessor_account_id(), self.owner_id, "Unauthorize");
+        let dead_letter = self.dead_letter.remove(&id).expect("dead letter not exist");
+        self.send_error_response(
+            &dead_letter.context,
+            SessionError::new(SessionError::DISCARDED, &dead_letter.reason),
+        );
+    }
+
+    fn receive_in_order(
+        &mut self,
+        sequence: u64,
+        payload: Payload,
+        context: Context,
+    ) -> Vec<(Payload, Context)> {
+        let key = (context.from_chain.clone(), context.sender.clone());
+        let next = self.inbound_sequence.get(&key).unwrap_or(0);
+        assert!(sequence >= next, "Message {} already delivered", sequence);
+        assert!(
+            sequence - next < INBOUND_REORDER_WINDOW,
+            "Message {} beyond the reorder window",
+            sequence
+        );
+        let mut buffered = self.sequence_buffered.get(&key).unwrap_or_default();
+        if sequence > next {
+            assert!(
+                !buffered.contains(&sequence),
+                "Message {} already received",
+                sequence
+            );
+            self.sequence_buffer
+                .insert(&(key.clone(), sequence), &(payload, context));
+            buffered.push(sequence);
+            self.sequence_buffered.insert(&key, &buffered);
+            return Vec::new();
+        }
+        self.take_buffered(key, next + 1, buffered, vec![(payload, context)])
+    }
+
+    /// Appends to `messages` the buffered messages of a channel following each other from
+    /// `next`, and moves the channel on past them.
+    fn take_buffered(
+        &mut self,
+        key: ChannelKey,
+        mut next: u64,
+        mut buffered: Vec<u64>,
+        mut messages: Vec<(Payload, Context)>,
+    ) -> Vec<(Payload, Context)> {
+        while let Some(position) = buffered.iter().position(|&sequence| sequence == next) {
+            buffered.swap_remove(position);
+            messages.push(
+                self.sequence_buffer
+                    .remove(&(key.clone(), next))
+                    .expect("buffered message not exist"),
+            );
+            next += 1;
+        }
+        if buffered.is_empty() {
+            self.sequence_buffered.remove(&key);
+        } else {
+            self.sequence_buffered.insert(&key, &buffered);
+        }
+        self.inbound_sequence.insert(&key, &next);
+        messages
+    }
+
+    fn receive_fragment(&mut self, context: &Context, fragment: Fragment) -> Option<Payload> {
+        let key = (
+            context.from_chain.clone(),
+            context.sender.clone(),
+            fragment.transfer_id,
+        );
+        let mut transfer = match self.chunk_transfer.get(&key) {
+            // Late fragment of a transfer given up, its fragments are discarded.
+            Some(transfer) if self.is_transfer_expired(&transfer) => {
+                self.remove_transfer(&key, &transfer);
+                events::emit(
+                    "transfer_expired",
+                    json!({ "from_chain": key.0, "transfer_id": hex::encode(key.2) }),
+                );
+                return None;
+            }
+            Some(transfer) => transfer,
+            None => ChunkTransfer {
+                action: context.action.clone(),
+                total: fragment.total,
+                received: Vec::new(),
+                hash: fragment.hash,
+                started_at: env::block_timestamp(),
+            },
+        };
+        assert!(
+            fragment.total <= MAX_TRANSFER_FRAGMENTS,
+            "Transfer of more than {} fragments",
+            MAX_TRANSFER_FRAGMENTS
+        );
+        assert!(
+            transfer.action == context.action
+                && transfer.total == fragment.total
+                && transfer.hash == fragment.hash,
+            "Fragment not match the transfer"
+        );
+        if transfer.received.contains(&fragment.index) {
+            // Delivered again, already stored.
+            return None;
+        }
+        self.chunk_fragment
+            .insert(&(key.clone(), fragment.index), &fragment.data);
+        transfer.received.push(fragment.index);
+        if (transfer.received.len() as u32) < transfer.total {
+            self.chunk_transfer.insert(&key, &transfer);
+            return None;
+        }
+        self.chunk_transfer.remove(&key);
+        let data = (0..transfer.total)
+            .map(|index| self.chunk_fragment.remove(&(key.clone(), index)).unwrap())
+            .collect();
+        match Fragment::join::<NearHasher>(&transfer.hash, data) {
+            Ok(payload) => Some(payload),
+            Err(err) => env::panic_str(&format!("Invalid payload: {}", err)),
+        }
+    }
+
+    fn assert_inbound_sender(&mut self, context: &Context, count_call: bool) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.omni_chain_contract_id,
+            "Process by cross chain contract"
+        );
+        self.check_permission(
+            &context.from_chain,
+            &context.sender,
+            &context.action,
+            count_call,
+        );
+        self.assert_inbound_rate(context);
+    }
+
+    fn assert_payload_schema(&self, payload: &Payload, action: &String) {
+        if let Some(schema) = self.action_schema.get(action) {
+            if let Err(err) = schema.validate(payload) {
+                env::panic_str(&format!("Invalid payload: {}", err));
+            }
+        }
+    }
+
+    pub fn assert_register_permitted_contract(
+        &mut self,
+        chain_name: &String,
+        sender: &Vec<u8>,
+        action: &String,
+    ) {
+        self.check_permission(chain_name, sender, action, true);
+    }
+
+    fn check_permission(
+        &mut self,
         chain_name: &String,
         sender: &Vec<u8>,
         action: &String,
+        count_call: bool,
     ) {
-        let key = (chain_name.clone(), sender.clone());
-        let actions = self.permitted_contract.get(&key).unwrap_or(Vec::new());
-        assert!(actions.contains(action), "{} not register", action);
+        let key = (chain_name.clone(), sender.clone(), action.clone());
+        let registered = self.permitted_contract.contains(&key);
+        // Most fields set wins, deny before allow; a registered permission has all three set.
+        let rule = self
+            .permission_rules
+            .iter()
+            .filter(|rule| rule.matches(chain_name, sender, action))
+            .max_by_key(|rule| (rule.specificity(), !rule.allow));
+        match rule {
+            Some(rule) if !rule.allow && (!registered || rule.specificity() == 3) => {
+                env::panic_str(&format!("{} denied", action))
+            }
+            Some(_) if !registered => return,
+            // An allowed registered permission keeps its limits, whichever rule matched.
+            _ => assert!(registered, "{} not register", action),
+        }
+        if let Some(mut limit) = self.permission_limit.get(&key) {
+            if let Some(expires_at) = limit.expires_at {
+                assert!(
+                    env::block_timestamp() < expires_at.0,
+                    "Permission of {} expired",
+                    action
+                );
+            }
+            if let Some(remaining_calls) = limit.remaining_calls {
+                assert!(remaining_calls > 0, "Permission of {} used up", action);
+                if count_call {
+                    limit.remaining_calls = Some(remaining_calls - 1);
+                    self.permission_limit.insert(&key, &limit);
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,17 @@
+use near_sdk::env;
+use near_sdk::serde_json::{json, Value};
+
+const EVENT_STANDARD: &str = "omnichain";
+
+const EVENT_VERSION: &str = "1.0.0";
+
+/// Log an event of the SDK in the NEP-297 format, so indexers can follow it.
+pub(crate) fn emit(event: &str, data: Value) {
+    let event = json!({
+        "standard": EVENT_STANDARD,
+        "version": EVENT_VERSION,
+        "event": event,
+        "data": [data],
+    });
+    env::log_str(&format!("EVENT_JSON:{}", event));
+}
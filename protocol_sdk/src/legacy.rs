@@ -0,0 +1,105 @@
+use crate::core_impl::{
+    OmniChain, StorageKey, DEFAULT_CHUNK_TRANSFER_TIMEOUT, DEFAULT_MAX_SEND_ATTEMPTS,
+};
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::{LookupMap, LookupSet, UnorderedMap, UnorderedSet};
+use near_sdk::{AccountId, IntoStorageKey};
+use protocol_types::DstContract;
+use std::collections::HashMap;
+
+/// Layout of `OmniChain` in the first release of the SDK, with only the registries, each
+/// chain keeping its contracts in one `HashMap` and each sender its actions in one `Vec`.
+/// Contracts deployed with it store it without version, see `VersionedOmniChain`.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct OmniChainV0 {
+    pub owner_id: AccountId,
+    pub omni_chain_contract_id: AccountId,
+    pub destination_contract: UnorderedMap<String, HashMap<String, DstContract>>,
+    pub permitted_contract: UnorderedMap<(String, Vec<u8>), Vec<String>>,
+}
+
+impl OmniChainV0 {
+    /**
+     * Move the destination contracts and permissions to the current layout, adding the
+     * other collections and settings empty or with their defaults
+     * @param destination_contract_prefix - prefix the contract created `OmniChain` with
+     * @param permitted_contract_prefix - prefix the contract created `OmniChain` with
+     */
+    pub fn migrate<S, T>(
+        mut self,
+        destination_contract_prefix: S,
+        permitted_contract_prefix: T,
+    ) -> OmniChain
+    where
+        S: IntoStorageKey,
+        T: IntoStorageKey,
+    {
+        // The new collections reuse the prefixes, so the old entries go first.
+        let destinations: Vec<_> = self.destination_contract.iter().collect();
+        self.destination_contract.clear();
+        let permissions: Vec<_> = self.permitted_contract.iter().collect();
+        self.permitted_contract.clear();
+
+        let mut omni_chain = OmniChain {
+            owner_id: self.owner_id,
+            omni_chain_contract_id: self.omni_chain_contract_id,
+            destination_contract: LookupMap::new(destination_contract_prefix),
+            permitted_contract: LookupSet::new(permitted_contract_prefix),
+            action_schema: UnorderedMap::new(StorageKey::ActionSchema.prefixed()),
+            max_payload_size: UnorderedMap::new(StorageKey::MaxPayloadSize.prefixed()),
+            chunk_transfer: UnorderedMap::new(StorageKey::ChunkTransfer.prefixed()),
+            chunk_fragment: LookupMap::new(StorageKey::ChunkFragment.prefixed()),
+            outbound_sequence: UnorderedMap::new(StorageKey::OutboundSequence.prefixed()),
+            inbound_sequence: UnorderedMap::new(StorageKey::InboundSequence.prefixed()),
+            sequence_buffer: LookupMap::new(StorageKey::SequenceBuffer.prefixed()),
+            outbound_message: UnorderedMap::new(StorageKey::OutboundMessage.prefixed()),
+            failed_send: UnorderedMap::new(StorageKey::FailedSend.prefixed()),
+            next_failed_send_id: 0,
+            outbound_log_enabled: false,
+            response_timeout: None,
+            max_send_attempts: DEFAULT_MAX_SEND_ATTEMPTS,
+            dead_letter_enabled: false,
+            next_inbound_id: 0,
+            inbound_message: LookupMap::new(StorageKey::InboundMessage.prefixed()),
+            dead_letter: UnorderedMap::new(StorageKey::DeadLetter.prefixed()),
+            next_correlation_id: 0,
+            gather: UnorderedMap::new(StorageKey::Gather.prefixed()),
+            gather_message: LookupMap::new(StorageKey::GatherMessage.prefixed()),
+            dst_route: UnorderedMap::new(StorageKey::DstRoute.prefixed()),
+            dst_candidates: UnorderedMap::new(StorageKey::DstCandidates.prefixed()),
+            permission_limit: UnorderedMap::new(StorageKey::PermissionLimit.prefixed()),
+            inbound_rate_limit: UnorderedMap::new(StorageKey::InboundRateLimit.prefixed()),
+            outbound_rate_limit: UnorderedMap::new(StorageKey::OutboundRateLimit.prefixed()),
+            inbound_rate_usage: LookupMap::new(StorageKey::InboundRateUsage.prefixed()),
+            outbound_rate_usage: LookupMap::new(StorageKey::OutboundRateUsage.prefixed()),
+            permission_rules: Vec::new(),
+            dst_contract_index: UnorderedSet::new(StorageKey::DstContractIndex.prefixed()),
+            permitted_contract_index: UnorderedSet::new(
+                StorageKey::PermittedContractIndex.prefixed(),
+            ),
+            next_transfer_nonce: 0,
+            chunk_transfer_timeout: DEFAULT_CHUNK_TRANSFER_TIMEOUT,
+            outbound_by_chain: LookupMap::new(StorageKey::OutboundByChain.prefixed()),
+            outbound_chain_count: LookupMap::new(StorageKey::OutboundChainCount.prefixed()),
+            outbound_by_sender: LookupMap::new(StorageKey::OutboundBySender.prefixed()),
+            outbound_sender_count: LookupMap::new(StorageKey::OutboundSenderCount.prefixed()),
+            sequence_buffered: LookupMap::new(StorageKey::SequenceBuffered.prefixed()),
+            next_rule_id: 0,
+        };
+        for (chain_name, contracts) in destinations {
+            for (action_name, contract) in contracts {
+                let key = (chain_name.clone(), action_name);
+                omni_chain.destination_contract.insert(&key, &contract);
+                omni_chain.dst_contract_index.insert(&key);
+            }
+        }
+        for ((chain_name, sender), actions) in permissions {
+            for action_name in actions {
+                let key = (chain_name.clone(), sender.clone(), action_name);
+                omni_chain.permitted_contract.insert(&key);
+                omni_chain.permitted_contract_index.insert(&key);
+            }
+        }
+        omni_chain
+    }
+}
@@ -1,9 +1,25 @@
 pub mod core_impl;
+mod events;
+pub mod legacy;
 pub mod macros;
-mod types;
+mod versioned;
 
 pub use self::core_impl::OmniChain;
-pub use self::types::*;
+pub use self::versioned::VersionedOmniChain;
+pub use protocol_sdk_macros::cross_chain_interface;
+pub use protocol_types::*;
+
+use near_sdk::json_types::U64;
+use near_sdk::{env, AccountId, Promise};
+
+/// Hashes messages with the NEAR runtime, e.g. `message.to_hash::<NearHasher>()`.
+pub struct NearHasher;
+
+impl Hasher for NearHasher {
+    fn sha256(data: &[u8]) -> Vec<u8> {
+        env::sha256(data)
+    }
+}
 
 pub trait RegisterCore {
     fn register_permitted_contract(
@@ -11,8 +27,20 @@ pub trait RegisterCore {
         chain_name: String,
         sender: String,
         action_name: String,
+        expires_at: Option<U64>,
+        max_calls: Option<u64>,
     );
 
+    fn get_permission_allowance(
+        &self,
+        chain_name: String,
+        sender: String,
+        action_name: String,
+    ) -> Option<core_impl::PermissionLimit>;
+
+    fn get_permitted_contracts(&self, from_index: u64, limit: u64)
+        -> Vec<core_impl::PermissionKey>;
+
     fn register_dst_contract(
         &mut self,
         action_name: String,
@@ -20,4 +48,140 @@ pub trait RegisterCore {
         contract_address: String,
         contract_action_name: String,
     );
+
+    fn get_registered_dst_contracts(
+        &self,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<(core_impl::DstKey, DstContract)>;
+}
+
+pub trait SendResultCore {
+    fn set_outbound_log(&mut self, enabled: bool, response_timeout: Option<U64>);
+
+    fn get_outbound_message(
+        &self,
+        chain_name: String,
+        id: U128,
+    ) -> Option<core_impl::OutboundMessage>;
+
+    fn get_outbound_messages(
+        &self,
+        chain_name: Option<String>,
+        sender: Option<AccountId>,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<(U128, core_impl::OutboundMessage)>;
+
+    fn retry_message(&mut self, failed_id: U64, gas: Option<U64>) -> Promise;
+
+    fn discard_failed_message(&mut self, failed_id: U64);
+
+    fn set_max_send_attempts(&mut self, max_attempts: u32);
+
+    fn get_failed_sends(&self, from_index: u64, limit: u64) -> Vec<(u64, core_impl::FailedSend)>;
+}
+
+pub trait SchemaCore {
+    fn register_action_schema(&mut self, action_name: String, schema: PayloadSchema);
+
+    fn remove_action_schema(&mut self, action_name: String);
+
+    fn get_action_schemas(&self) -> Vec<(String, PayloadSchema)>;
+}
+
+pub trait PayloadLimitCore {
+    fn set_max_payload_size(&mut self, chain_name: String, max_size: Option<u32>);
+
+    fn get_max_payload_size(&self, chain_name: String) -> Option<u32>;
+
+    fn set_chunk_transfer_timeout(&mut self, timeout: U64);
+
+    fn clear_expired_transfers(&mut self, limit: u64) -> u64;
+}
+
+pub trait OrderedChannelCore {
+    fn get_next_inbound_sequence(&self, chain_name: String, sender: String) -> u64;
+
+    fn get_next_outbound_sequence(&self, chain_name: String, contract_address: String) -> u64;
+
+    fn skip_inbound_sequence(&mut self, chain_name: String, sender: String, sequence: u64);
+}
+
+pub trait DeadLetterCore {
+    fn set_dead_letter_mode(&mut self, enabled: bool);
+
+    fn replay_dead_letter(&mut self, id: U64);
+
+    fn discard_dead_letter(&mut self, id: U64);
+
+    fn get_dead_letters(&self, from_index: u64, limit: u64) -> Vec<(u64, core_impl::DeadLetter)>;
+}
+
+pub trait ScatterGatherCore {
+    fn complete_gather(&mut self, correlation_id: U64);
+
+    fn get_gather(&self, correlation_id: U64) -> Option<core_impl::Gather>;
+}
+
+pub trait RouteCore {
+    fn register_dst_route(&mut self, chain_name: String, hops: Vec<(String, String, String)>);
+
+    fn get_dst_route(&self, chain_name: String) -> Vec<(String, DstContract)>;
+}
+
+pub trait FailoverCore {
+    fn add_dst_contract(
+        &mut self,
+        chain_name: String,
+        action_name: String,
+        contract_address: String,
+        contract_action_name: String,
+        priority: u32,
+    );
+
+    fn remove_dst_contract(
+        &mut self,
+        chain_name: String,
+        action_name: String,
+        contract_address: String,
+    );
+
+    fn set_dst_contract_health(
+        &mut self,
+        chain_name: String,
+        action_name: String,
+        contract_address: String,
+        healthy: bool,
+    );
+
+    fn get_dst_contracts(
+        &self,
+        chain_name: String,
+        action_name: String,
+    ) -> Vec<core_impl::DstCandidate>;
+}
+
+pub trait RateLimitCore {
+    fn set_inbound_rate_limit(&mut self, chain_name: String, limit: Option<core_impl::RateLimit>);
+
+    fn set_outbound_rate_limit(&mut self, chain_name: String, limit: Option<core_impl::RateLimit>);
+
+    fn get_inbound_rate_limit(&self, chain_name: String) -> Option<core_impl::RateLimit>;
+
+    fn get_outbound_rate_limit(&self, chain_name: String) -> Option<core_impl::RateLimit>;
+}
+
+pub trait PermissionRuleCore {
+    fn add_permission_rule(
+        &mut self,
+        chain_name: Option<String>,
+        sender: Option<String>,
+        action_name: Option<String>,
+        allow: bool,
+    ) -> U64;
+
+    fn remove_permission_rule(&mut self, id: U64);
+
+    fn get_permission_rules(&self) -> Vec<core_impl::PermissionRule>;
 }
@@ -27,9 +27,553 @@ macro_rules! impl_omni_chain_register {
                 chain_name: String,
                 sender: String,
                 action_name: String,
+                expires_at: Option<near_sdk::json_types::U64>,
+                max_calls: Option<u64>,
             ) {
+                self.$cross.register_permitted_contract(
+                    chain_name,
+                    sender,
+                    action_name,
+                    expires_at,
+                    max_calls,
+                );
+            }
+
+            fn get_permission_allowance(
+                &self,
+                chain_name: String,
+                sender: String,
+                action_name: String,
+            ) -> Option<$crate::core_impl::PermissionLimit> {
+                self.$cross
+                    .get_permission_allowance(chain_name, sender, action_name)
+            }
+
+            fn get_permitted_contracts(
+                &self,
+                from_index: u64,
+                limit: u64,
+            ) -> Vec<$crate::core_impl::PermissionKey> {
+                self.$cross.get_permitted_contracts(from_index, limit)
+            }
+
+            fn get_registered_dst_contracts(
+                &self,
+                from_index: u64,
+                limit: u64,
+            ) -> Vec<($crate::core_impl::DstKey, $crate::DstContract)> {
+                self.$cross.get_registered_dst_contracts(from_index, limit)
+            }
+        }
+
+        $crate::impl_omni_chain_send_result!($contract, $cross);
+    };
+}
+
+/// The `migrate` entry point, upgrading the state of `OmniChain` in place after the
+/// contract is deployed with a release of the SDK changing its layout. The contract keeps
+/// `OmniChain` as a `VersionedOmniChain`, created with the prefixes given here. It also
+/// reads the plain `OmniChain` of contracts deployed before the versioning, as long as
+/// their other fields are unchanged.
+#[macro_export]
+macro_rules! impl_omni_chain_migrate {
+    ($contract: ident, $cross: ident, $destination_contract_prefix: expr, $permitted_contract_prefix: expr) => {
+        #[near_bindgen]
+        impl $contract {
+            #[private]
+            #[init(ignore_state)]
+            pub fn migrate() -> Self {
+                let mut this: Self = near_sdk::env::state_read().expect("contract not initialized");
+                this.$cross = this
+                    .$cross
+                    .migrate($destination_contract_prefix, $permitted_contract_prefix);
+                this
+            }
+        }
+    };
+}
+
+/// The callback recording the result of each message sent by `OmniChain`, the log of sent
+/// messages, and the view and retry of failed ones. Included by `impl_omni_chain_register`.
+#[macro_export]
+macro_rules! impl_omni_chain_send_result {
+    ($contract: ident, $cross: ident) => {
+        use $crate::SendResultCore;
+
+        #[near_bindgen]
+        impl $contract {
+            #[private]
+            pub fn on_message_sent(
+                &mut self,
+                sender: near_sdk::AccountId,
+                to_chain: String,
+                content: $crate::Content,
+                session: Option<$crate::Session>,
+                attempts: u32,
+            ) -> Option<$crate::U128> {
+                self.$cross
+                    .on_message_sent(sender, to_chain, content, session, attempts)
+            }
+
+            #[private]
+            pub fn on_multicast_sent(
+                &self,
+                chains: Vec<String>,
+                unregistered: Vec<String>,
+            ) -> $crate::core_impl::MulticastResult {
+                self.$cross.on_multicast_sent(chains, unregistered)
+            }
+        }
+
+        #[near_bindgen]
+        impl SendResultCore for $contract {
+            fn set_outbound_log(
+                &mut self,
+                enabled: bool,
+                response_timeout: Option<near_sdk::json_types::U64>,
+            ) {
+                self.$cross.set_outbound_log(enabled, response_timeout);
+            }
+
+            fn get_outbound_message(
+                &self,
+                chain_name: String,
+                id: $crate::U128,
+            ) -> Option<$crate::core_impl::OutboundMessage> {
+                self.$cross.get_outbound_message(chain_name, id)
+            }
+
+            fn get_outbound_messages(
+                &self,
+                chain_name: Option<String>,
+                sender: Option<near_sdk::AccountId>,
+                from_index: u64,
+                limit: u64,
+            ) -> Vec<($crate::U128, $crate::core_impl::OutboundMessage)> {
+                self.$cross
+                    .get_outbound_messages(chain_name, sender, from_index, limit)
+            }
+
+            fn retry_message(
+                &mut self,
+                failed_id: near_sdk::json_types::U64,
+                gas: Option<near_sdk::json_types::U64>,
+            ) -> near_sdk::Promise {
+                self.$cross
+                    .retry_message(failed_id.0, gas.map(|gas| near_sdk::Gas(gas.0)))
+            }
+
+            fn discard_failed_message(&mut self, failed_id: near_sdk::json_types::U64) {
+                self.$cross.discard_failed_message(failed_id.0);
+            }
+
+            fn set_max_send_attempts(&mut self, max_attempts: u32) {
+                self.$cross.set_max_send_attempts(max_attempts);
+            }
+
+            fn get_failed_sends(
+                &self,
+                from_index: u64,
+                limit: u64,
+            ) -> Vec<(u64, $crate::core_impl::FailedSend)> {
                 self.$cross
-                    .register_permitted_contract(chain_name, sender, action_name);
+                    .failed_send
+                    .iter()
+                    .skip(from_index as usize)
+                    .take(limit as usize)
+                    .collect()
+            }
+        }
+    };
+}
+
+/// The single entry point for messages from other chains. `on_cross_chain_message` checks
+/// the message is delivered by the cross-chain contract, that the sender is permitted to
+/// call `context.action` and that the payload matches the schema declared for the action,
+/// then dispatches to the handler registered for the action. Fragments of chunked transfers
+/// are buffered, and the handler is called once with the reassembled payload. Messages of
+/// ordered channels received ahead of their turn are buffered too, and dispatched in the
+/// same call as the message they wait for.
+/// Handlers take `(payload: Payload, context: Context)` and should not be exposed by
+/// `#[near_bindgen]` themselves. They run in a separate receipt, and if one panics on a
+/// request, an error response is sent back to the requester. Error responses to requests
+/// of this contract go to the optional `on_error` hook, taking
+/// `(error: SessionError, context: Context)`, and are logged without it.
+#[macro_export]
+macro_rules! impl_omni_chain_router {
+    (@on_error $self: ident, $error: ident, $context: ident, $on_error: ident) => {
+        $self.$on_error($error, $context)
+    };
+    (@on_error $self: ident, $error: ident, $context: ident) => {
+        near_sdk::env::log_str(&format!(
+            "Request {} to {} failed with {}",
+            $context.session.id.0, $context.from_chain, $error
+        ))
+    };
+    ($contract: ident, $cross: ident, { $($action: literal => $handler: ident),* $(,)? }
+        $(, on_error => $on_error: ident)? $(,)?) => {
+        #[near_bindgen]
+        impl $contract {
+            pub fn on_cross_chain_message(
+                &mut self,
+                payload: $crate::Payload,
+                context: $crate::Context,
+            ) {
+                let messages = self.$cross.accept_inbound_message(payload, context);
+                self.$cross.dispatch_inbound_messages(messages);
+            }
+
+            #[private]
+            pub fn dispatch_cross_chain_message(
+                &mut self,
+                payload: $crate::Payload,
+                context: $crate::Context,
+            ) {
+                if context.session.session_type == $crate::Session::ERROR {
+                    let error = $crate::SessionError::from_payload(&payload).unwrap();
+                    $crate::impl_omni_chain_router!(@on_error self, error, context $(, $on_error)?);
+                } else {
+                    match context.action.as_str() {
+                        $($action => self.$handler(payload, context),)*
+                        _ => near_sdk::env::panic_str("action not register"),
+                    }
+                }
+            }
+
+            #[private]
+            pub fn on_cross_chain_message_dispatched(
+                &mut self,
+                context: $crate::Context,
+                inbound_id: Option<u64>,
+            ) {
+                self.$cross.on_message_dispatched(context, inbound_id);
+            }
+        }
+    };
+}
+
+/// Declaring and viewing the payload schemas of the actions of this contract.
+#[macro_export]
+macro_rules! impl_omni_chain_schema {
+    ($contract: ident, $cross: ident) => {
+        use $crate::SchemaCore;
+
+        #[near_bindgen]
+        impl SchemaCore for $contract {
+            fn register_action_schema(
+                &mut self,
+                action_name: String,
+                schema: $crate::PayloadSchema,
+            ) {
+                self.$cross.register_action_schema(action_name, schema);
+            }
+
+            fn remove_action_schema(&mut self, action_name: String) {
+                self.$cross.remove_action_schema(action_name);
+            }
+
+            fn get_action_schemas(&self) -> Vec<(String, $crate::PayloadSchema)> {
+                self.$cross.action_schema.to_vec()
+            }
+        }
+    };
+}
+
+/// Configuring and viewing the max payload size of messages sent to each chain, and the
+/// expiry of partial chunked transfers received.
+#[macro_export]
+macro_rules! impl_omni_chain_payload_limit {
+    ($contract: ident, $cross: ident) => {
+        use $crate::PayloadLimitCore;
+
+        #[near_bindgen]
+        impl PayloadLimitCore for $contract {
+            fn set_max_payload_size(&mut self, chain_name: String, max_size: Option<u32>) {
+                self.$cross.set_max_payload_size(chain_name, max_size);
+            }
+
+            fn get_max_payload_size(&self, chain_name: String) -> Option<u32> {
+                self.$cross.max_payload_size.get(&chain_name)
+            }
+
+            fn set_chunk_transfer_timeout(&mut self, timeout: near_sdk::json_types::U64) {
+                self.$cross.set_chunk_transfer_timeout(timeout);
+            }
+
+            fn clear_expired_transfers(&mut self, limit: u64) -> u64 {
+                self.$cross.clear_expired_transfers(limit)
+            }
+        }
+    };
+}
+
+/// Viewing the next sequence numbers of the ordered channels of this contract, and skipping
+/// inbound messages which won't arrive. Buffered messages are dispatched to the handlers of
+/// `impl_omni_chain_router`.
+#[macro_export]
+macro_rules! impl_omni_chain_ordered_channel {
+    ($contract: ident, $cross: ident) => {
+        use $crate::OrderedChannelCore;
+
+        #[near_bindgen]
+        impl OrderedChannelCore for $contract {
+            fn get_next_inbound_sequence(&self, chain_name: String, sender: String) -> u64 {
+                self.$cross.get_next_inbound_sequence(chain_name, sender)
+            }
+
+            fn get_next_outbound_sequence(
+                &self,
+                chain_name: String,
+                contract_address: String,
+            ) -> u64 {
+                self.$cross
+                    .get_next_outbound_sequence(chain_name, contract_address)
+            }
+
+            fn skip_inbound_sequence(&mut self, chain_name: String, sender: String, sequence: u64) {
+                let messages = self
+                    .$cross
+                    .skip_inbound_sequence(chain_name, sender, sequence);
+                self.$cross.dispatch_inbound_messages(messages);
+            }
+        }
+    };
+}
+
+/// Dead-letter mode, and inspecting, replaying and discarding the inbound messages whose
+/// handler failed. Requires `impl_omni_chain_router`.
+#[macro_export]
+macro_rules! impl_omni_chain_dead_letter {
+    ($contract: ident, $cross: ident) => {
+        use $crate::DeadLetterCore;
+
+        #[near_bindgen]
+        impl DeadLetterCore for $contract {
+            fn set_dead_letter_mode(&mut self, enabled: bool) {
+                self.$cross.set_dead_letter_mode(enabled);
+            }
+
+            fn replay_dead_letter(&mut self, id: near_sdk::json_types::U64) {
+                self.$cross.replay_dead_letter(id.0);
+            }
+
+            fn discard_dead_letter(&mut self, id: near_sdk::json_types::U64) {
+                self.$cross.discard_dead_letter(id.0);
+            }
+
+            fn get_dead_letters(
+                &self,
+                from_index: u64,
+                limit: u64,
+            ) -> Vec<(u64, $crate::core_impl::DeadLetter)> {
+                self.$cross
+                    .dead_letter
+                    .iter()
+                    .skip(from_index as usize)
+                    .take(limit as usize)
+                    .collect()
+            }
+        }
+    };
+}
+
+/// Completing and viewing scatter-gather requests, and the callback recording the ids of
+/// their messages. Required by contracts using `OmniChain::scatter_request`.
+#[macro_export]
+macro_rules! impl_omni_chain_scatter_gather {
+    ($contract: ident, $cross: ident) => {
+        use $crate::ScatterGatherCore;
+
+        #[near_bindgen]
+        impl $contract {
+            #[private]
+            pub fn on_scatter_sent(&mut self, correlation_id: u64, chains: Vec<String>) {
+                self.$cross.on_scatter_sent(correlation_id, chains);
+            }
+        }
+
+        #[near_bindgen]
+        impl ScatterGatherCore for $contract {
+            fn complete_gather(&mut self, correlation_id: near_sdk::json_types::U64) {
+                self.$cross.complete_gather(correlation_id.0);
+            }
+
+            fn get_gather(
+                &self,
+                correlation_id: near_sdk::json_types::U64,
+            ) -> Option<$crate::core_impl::Gather> {
+                self.$cross.gather.get(&correlation_id.0)
+            }
+        }
+    };
+}
+
+/// Registering and viewing routes through forwarders on intermediate chains, and the
+/// `forward_message` handler for contracts acting as forwarder, to be registered in
+/// `impl_omni_chain_router`.
+#[macro_export]
+macro_rules! impl_omni_chain_route {
+    ($contract: ident, $cross: ident) => {
+        use $crate::RouteCore;
+
+        #[near_bindgen]
+        impl RouteCore for $contract {
+            fn register_dst_route(
+                &mut self,
+                chain_name: String,
+                hops: Vec<(String, String, String)>,
+            ) {
+                self.$cross.register_dst_route(chain_name, hops);
+            }
+
+            fn get_dst_route(&self, chain_name: String) -> Vec<(String, $crate::DstContract)> {
+                self.$cross.dst_route.get(&chain_name).unwrap_or_default()
+            }
+        }
+
+        impl $contract {
+            pub fn forward_message(&mut self, payload: $crate::Payload, _context: $crate::Context) {
+                self.$cross.forward_message(payload);
+            }
+        }
+    };
+}
+
+/// Managing and viewing several destination contracts per action, with failover to the
+/// next contract when one is marked unhealthy.
+#[macro_export]
+macro_rules! impl_omni_chain_failover {
+    ($contract: ident, $cross: ident) => {
+        use $crate::FailoverCore;
+
+        #[near_bindgen]
+        impl FailoverCore for $contract {
+            fn add_dst_contract(
+                &mut self,
+                chain_name: String,
+                action_name: String,
+                contract_address: String,
+                contract_action_name: String,
+                priority: u32,
+            ) {
+                self.$cross.add_dst_contract(
+                    chain_name,
+                    action_name,
+                    contract_address,
+                    contract_action_name,
+                    priority,
+                );
+            }
+
+            fn remove_dst_contract(
+                &mut self,
+                chain_name: String,
+                action_name: String,
+                contract_address: String,
+            ) {
+                self.$cross
+                    .remove_dst_contract(chain_name, action_name, contract_address);
+            }
+
+            fn set_dst_contract_health(
+                &mut self,
+                chain_name: String,
+                action_name: String,
+                contract_address: String,
+                healthy: bool,
+            ) {
+                self.$cross.set_dst_contract_health(
+                    chain_name,
+                    action_name,
+                    contract_address,
+                    healthy,
+                );
+            }
+
+            fn get_dst_contracts(
+                &self,
+                chain_name: String,
+                action_name: String,
+            ) -> Vec<$crate::core_impl::DstCandidate> {
+                self.$cross
+                    .dst_candidates
+                    .get(&(chain_name, action_name))
+                    .unwrap_or_default()
+            }
+        }
+    };
+}
+
+/// Configuring and viewing the rate limits of inbound and outbound messages of each chain.
+#[macro_export]
+macro_rules! impl_omni_chain_rate_limit {
+    ($contract: ident, $cross: ident) => {
+        use $crate::RateLimitCore;
+
+        #[near_bindgen]
+        impl RateLimitCore for $contract {
+            fn set_inbound_rate_limit(
+                &mut self,
+                chain_name: String,
+                limit: Option<$crate::core_impl::RateLimit>,
+            ) {
+                self.$cross.set_inbound_rate_limit(chain_name, limit);
+            }
+
+            fn set_outbound_rate_limit(
+                &mut self,
+                chain_name: String,
+                limit: Option<$crate::core_impl::RateLimit>,
+            ) {
+                self.$cross.set_outbound_rate_limit(chain_name, limit);
+            }
+
+            fn get_inbound_rate_limit(
+                &self,
+                chain_name: String,
+            ) -> Option<$crate::core_impl::RateLimit> {
+                self.$cross.inbound_rate_limit.get(&chain_name)
+            }
+
+            fn get_outbound_rate_limit(
+                &self,
+                chain_name: String,
+            ) -> Option<$crate::core_impl::RateLimit> {
+                self.$cross.outbound_rate_limit.get(&chain_name)
+            }
+        }
+    };
+}
+
+/// Managing and viewing the wildcard allow and deny rules of inbound messages.
+#[macro_export]
+macro_rules! impl_omni_chain_permission_rule {
+    ($contract: ident, $cross: ident) => {
+        use $crate::PermissionRuleCore;
+
+        #[near_bindgen]
+        impl PermissionRuleCore for $contract {
+            fn add_permission_rule(
+                &mut self,
+                chain_name: Option<String>,
+                sender: Option<String>,
+                action_name: Option<String>,
+                allow: bool,
+            ) -> near_sdk::json_types::U64 {
+                near_sdk::json_types::U64(self.$cross.add_permission_rule(
+                    chain_name,
+                    sender,
+                    action_name,
+                    allow,
+                ))
+            }
+
+            fn remove_permission_rule(&mut self, id: near_sdk::json_types::U64) {
+                self.$cross.remove_permission_rule(id.0);
+            }
+
+            fn get_permission_rules(&self) -> Vec<$crate::core_impl::PermissionRule> {
+                self.$cross.permission_rules.clone()
             }
         }
     };
@@ -0,0 +1,102 @@
+use crate::core_impl::OmniChain;
+use crate::events;
+use crate::legacy::OmniChainV0;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde_json::json;
+use near_sdk::IntoStorageKey;
+use std::io;
+use std::ops::{Deref, DerefMut};
+
+/// `OmniChain` stored with the version of its layout, so a contract keeps its state
+/// across SDK upgrades changing the layout. It dereferences to the current `OmniChain`,
+/// which it holds from `OmniChain::new` or once `migrate` has run after an upgrade.
+///
+/// Contracts deployed before the versioning store a plain `OmniChainV0`, which is read as
+/// `V0`. When a release changes `OmniChain`, its previous layout moves to `legacy` as the
+/// variant of the previous version, and a variant is added for the new one.
+#[derive(BorshSerialize)]
+#[allow(clippy::large_enum_variant)]
+pub enum VersionedOmniChain {
+    V0(OmniChainV0),
+    V1(OmniChain),
+}
+
+impl VersionedOmniChain {
+    pub const CURRENT_VERSION: u8 = 1;
+
+    pub fn version(&self) -> u8 {
+        match self {
+            VersionedOmniChain::V0(_) => 0,
+            VersionedOmniChain::V1(_) => 1,
+        }
+    }
+
+    /**
+     * Upgrade the state to the current layout, one version after the other
+     * @param destination_contract_prefix - prefix the contract created `OmniChain` with
+     * @param permitted_contract_prefix - prefix the contract created `OmniChain` with
+     */
+    pub fn migrate<S, T>(self, destination_contract_prefix: S, permitted_contract_prefix: T) -> Self
+    where
+        S: IntoStorageKey,
+        T: IntoStorageKey,
+    {
+        let from_version = self.version();
+        let omni_chain = match self {
+            VersionedOmniChain::V0(omni_chain) => {
+                omni_chain.migrate(destination_contract_prefix, permitted_contract_prefix)
+            }
+            VersionedOmniChain::V1(omni_chain) => return VersionedOmniChain::V1(omni_chain),
+        };
+        events::emit(
+            "state_migrated",
+            json!({ "from_version": from_version, "to_version": Self::CURRENT_VERSION }),
+        );
+        VersionedOmniChain::V1(omni_chain)
+    }
+}
+
+impl BorshDeserialize for VersionedOmniChain {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        // Every layout starts with the owner id, whose length is at most 64. Untagged, the
+        // state starts with its four length bytes, the last three zero; tagged, the tag is
+        // followed by the first length byte, which isn't.
+        if buf.len() >= 4 && buf[1..4] == [0, 0, 0] {
+            return Ok(VersionedOmniChain::V0(OmniChainV0::deserialize(buf)?));
+        }
+        match u8::deserialize(buf)? {
+            0 => Ok(VersionedOmniChain::V0(OmniChainV0::deserialize(buf)?)),
+            1 => Ok(VersionedOmniChain::V1(OmniChain::deserialize(buf)?)),
+            tag => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unexpected OmniChain version {}", tag),
+            )),
+        }
+    }
+}
+
+impl From<OmniChain> for VersionedOmniChain {
+    fn from(omni_chain: OmniChain) -> Self {
+        VersionedOmniChain::V1(omni_chain)
+    }
+}
+
+impl Deref for VersionedOmniChain {
+    type Target = OmniChain;
+
+    fn deref(&self) -> &OmniChain {
+        match self {
+            VersionedOmniChain::V1(omni_chain) => omni_chain,
+            _ => panic!("OmniChain state not migrated"),
+        }
+    }
+}
+
+impl DerefMut for VersionedOmniChain {
+    fn deref_mut(&mut self) -> &mut OmniChain {
+        match self {
+            VersionedOmniChain::V1(omni_chain) => omni_chain,
+            _ => panic!("OmniChain state not migrated"),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! Gas attached to the promises of `OmniChain`, checked against the gas the calls get.
+
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::{testing_env, Gas, PromiseResult, RuntimeFeesConfig, VMConfig};
+use protocol_sdk::core_impl::GAS_FOR_DISPATCH_CALLBACK;
+use protocol_sdk::{Content, Context, OmniChain, Payload, Session, Value, U128};
+
+fn setup_context(prepaid_gas: Gas) {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id("contract.near".parse().unwrap())
+        .predecessor_account_id("alice.near".parse().unwrap())
+        .prepaid_gas(prepaid_gas)
+        .build());
+}
+
+fn omni_chain() -> OmniChain {
+    OmniChain::new(
+        "owner.near".parse().unwrap(),
+        b"d".to_vec(),
+        b"p".to_vec(),
+        "omni.near".parse().unwrap(),
+    )
+}
+
+fn content(size: usize) -> Content {
+    let mut data = Payload::new();
+    data.set_item("data".to_string(), Value::Bytes(vec![0; size]));
+    Content {
+        contract: vec![1],
+        action: vec![2],
+        data,
+    }
+}
+
+#[test]
+fn send_gas_grows_with_content() {
+    setup_context(Gas(300_000_000_000_000));
+    omni_chain().call_cross("ETHEREUM".to_string(), content(10_000));
+}
+
+#[test]
+#[should_panic(expected = "Not enough gas to send")]
+fn send_without_gas_for_content() {
+    // Enough for a small message, not for 100 KB.
+    setup_context(Gas(20_000_000_000_000));
+    omni_chain().call_cross("ETHEREUM".to_string(), content(100_000));
+}
+
+#[test]
+fn respond_failed_handler_within_dispatch_callback_gas() {
+    let context = Context {
+        id: U128(1),
+        from_chain: "ETHEREUM".to_string(),
+        sender: vec![0xab; 32],
+        signer: vec![],
+        contract_id: "contract.near".to_string(),
+        action: "request".to_string(),
+        sqos: vec![],
+        session: Session {
+            id: U128(1),
+            session_type: Session::REQUEST,
+            callback: Some(vec![0xcd; 64]),
+            commitment: None,
+            answer: None,
+        },
+    };
+    let mut omni_chain = omni_chain();
+    testing_env!(
+        VMContextBuilder::new()
+            .current_account_id("contract.near".parse().unwrap())
+            .predecessor_account_id("contract.near".parse().unwrap())
+            .prepaid_gas(GAS_FOR_DISPATCH_CALLBACK)
+            .build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        Default::default(),
+        vec![PromiseResult::Failed],
+    );
+    omni_chain.on_message_dispatched(context, None);
+}
@@ -0,0 +1,169 @@
+//! Messages accepted by `OmniChain::accept_inbound_message`: chunked transfers and ordered
+//! channels.
+
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::{testing_env, AccountId};
+use protocol_sdk::core_impl::{INBOUND_REORDER_WINDOW, MAX_TRANSFER_FRAGMENTS};
+use protocol_sdk::{
+    Context, Fragment, NearHasher, OmniChain, Payload, Session, Value, SEQUENCE_ITEM, U128,
+};
+
+const OWNER: &str = "owner.near";
+const CROSS_CHAIN: &str = "omni.near";
+
+fn set_predecessor(account_id: &str, block_timestamp: u64) {
+    testing_env!(VMContextBuilder::new()
+        .current_account_id("contract.near".parse().unwrap())
+        .predecessor_account_id(account_id.parse::<AccountId>().unwrap())
+        .block_timestamp(block_timestamp)
+        .build());
+}
+
+/// An `OmniChain` accepting any message of `0xabcd` on ETHEREUM, called by the
+/// cross-chain contract.
+fn omni_chain() -> OmniChain {
+    set_predecessor(OWNER, 0);
+    let mut omni_chain = OmniChain::new(
+        OWNER.parse().unwrap(),
+        b"d".to_vec(),
+        b"p".to_vec(),
+        CROSS_CHAIN.parse().unwrap(),
+    );
+    omni_chain.add_permission_rule(
+        Some("ETHEREUM".to_string()),
+        Some("0xabcd".to_string()),
+        None,
+        true,
+    );
+    set_predecessor(CROSS_CHAIN, 0);
+    omni_chain
+}
+
+fn context() -> Context {
+    Context {
+        id: U128(1),
+        from_chain: "ETHEREUM".to_string(),
+        sender: vec![0xab, 0xcd],
+        signer: vec![],
+        contract_id: "contract.near".to_string(),
+        action: "receive".to_string(),
+        sqos: vec![],
+        session: Session {
+            id: U128(1),
+            session_type: 0,
+            callback: None,
+            commitment: None,
+            answer: None,
+        },
+    }
+}
+
+fn payload(size: usize) -> Payload {
+    let mut payload = Payload::new();
+    payload.set_item("data".to_string(), Value::Bytes(vec![7; size]));
+    payload
+}
+
+fn fragments(payload: &Payload, chunk_size: usize) -> Vec<Fragment> {
+    Fragment::split::<NearHasher>(payload, [1; 32], chunk_size)
+}
+
+#[test]
+fn reassemble_fragments_in_any_order() {
+    let mut omni_chain = omni_chain();
+    let full = payload(100);
+    let mut fragments = fragments(&full, 30);
+    fragments.reverse();
+    let last = fragments.pop().unwrap();
+    for fragment in fragments {
+        assert!(omni_chain
+            .accept_inbound_message(fragment.into_payload(), context())
+            .is_empty());
+    }
+    let messages = omni_chain.accept_inbound_message(last.into_payload(), context());
+    assert_eq!(messages, vec![(full, context())]);
+}
+
+#[test]
+#[should_panic(expected = "Transfer of more than 256 fragments")]
+fn reject_too_many_fragments() {
+    let mut omni_chain = omni_chain();
+    let mut fragment = fragments(&payload(10), 4).remove(0);
+    fragment.total = MAX_TRANSFER_FRAGMENTS + 1;
+    omni_chain.accept_inbound_message(fragment.into_payload(), context());
+}
+
+#[test]
+fn clear_expired_transfer_of_stored_fragments() {
+    let mut omni_chain = omni_chain();
+    let mut fragment = fragments(&payload(10), 4).remove(0);
+    fragment.total = MAX_TRANSFER_FRAGMENTS;
+    omni_chain.accept_inbound_message(fragment.into_payload(), context());
+    set_predecessor(CROSS_CHAIN, 2 * 24 * 60 * 60 * 1_000_000_000);
+    assert_eq!(omni_chain.clear_expired_transfers(10), 1);
+    assert_eq!(omni_chain.chunk_transfer.len(), 0);
+}
+
+fn ordered(sequence: u64) -> Payload {
+    let mut payload = payload(1);
+    payload.set_item(SEQUENCE_ITEM.to_string(), Value::Uint64(sequence));
+    payload
+}
+
+#[test]
+fn deliver_ordered_messages_in_sequence() {
+    let mut omni_chain = omni_chain();
+    assert!(omni_chain
+        .accept_inbound_message(ordered(2), context())
+        .is_empty());
+    assert!(omni_chain
+        .accept_inbound_message(ordered(1), context())
+        .is_empty());
+    let messages = omni_chain.accept_inbound_message(ordered(0), context());
+    assert_eq!(messages.len(), 3);
+    assert_eq!(
+        omni_chain.get_next_inbound_sequence("ETHEREUM".to_string(), "0xabcd".to_string()),
+        3
+    );
+}
+
+#[test]
+#[should_panic(expected = "Message 64 beyond the reorder window")]
+fn reject_message_beyond_reorder_window() {
+    let mut omni_chain = omni_chain();
+    omni_chain.accept_inbound_message(ordered(INBOUND_REORDER_WINDOW - 1), context());
+    omni_chain.accept_inbound_message(ordered(INBOUND_REORDER_WINDOW), context());
+}
+
+#[test]
+fn skip_to_buffered_messages() {
+    let mut omni_chain = omni_chain();
+    for sequence in [3, 5, 6] {
+        omni_chain.accept_inbound_message(ordered(sequence), context());
+    }
+    set_predecessor(OWNER, 0);
+    let messages =
+        omni_chain.skip_inbound_sequence("ETHEREUM".to_string(), "0xabcd".to_string(), 5);
+    assert_eq!(messages.len(), 2);
+    assert_eq!(
+        omni_chain.get_next_inbound_sequence("ETHEREUM".to_string(), "0xabcd".to_string()),
+        7
+    );
+    let key = ("ETHEREUM".to_string(), vec![0xab, 0xcd]);
+    assert!(omni_chain.sequence_buffer.get(&(key.clone(), 3)).is_none());
+    assert!(omni_chain.sequence_buffered.get(&key).is_none());
+}
+
+#[test]
+#[should_panic(expected = "receive not register")]
+fn remove_permission_rule_by_id() {
+    let mut omni_chain = omni_chain();
+    set_predecessor(OWNER, 0);
+    let deny = omni_chain.add_permission_rule(None, None, Some("other".to_string()), false);
+    assert_eq!(deny, 1);
+    omni_chain.remove_permission_rule(0);
+    assert_eq!(omni_chain.permission_rules.len(), 1);
+    assert_eq!(omni_chain.permission_rules[0].id.0, deny);
+    set_predecessor(CROSS_CHAIN, 0);
+    omni_chain.accept_inbound_message(payload(1), context());
+}
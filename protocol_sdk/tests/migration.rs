@@ -0,0 +1,133 @@
+//! Migrations of `OmniChain` states saved by previous releases of the SDK. Each snapshot
+//! holds, in hex, the Borsh encoding of `OmniChain` on its first line and the storage
+//! entries of its collections on the next ones, after registering a destination contract
+//! and a permission with the release.
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::{env, near_bindgen, testing_env, PanicOnDefault};
+use protocol_sdk::legacy::OmniChainV0;
+use protocol_sdk::{OmniChain, VersionedOmniChain};
+
+const V0_SNAPSHOT: &str = include_str!("snapshots/omni_chain_v0.txt");
+
+/// A contract deployed before the versioning, with a plain `OmniChain` in its state.
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Greeting {
+    omni_chain: VersionedOmniChain,
+    greeting: String,
+}
+
+protocol_sdk::impl_omni_chain_migrate!(Greeting, omni_chain, b"d".to_vec(), b"p".to_vec());
+
+fn setup_context() {
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id("owner.near".parse().unwrap())
+        .build());
+}
+
+/// Writes the storage entries of a snapshot and returns the encoded state.
+fn load_snapshot(snapshot: &str) -> Vec<u8> {
+    setup_context();
+    let mut lines = snapshot.lines();
+    let state = hex::decode(lines.next().unwrap()).unwrap();
+    for line in lines {
+        let (key, value) = line.split_once(' ').unwrap();
+        env::storage_write(&hex::decode(key).unwrap(), &hex::decode(value).unwrap());
+    }
+    state
+}
+
+fn migrate(state: VersionedOmniChain) -> VersionedOmniChain {
+    state.migrate(b"d".to_vec(), b"p".to_vec())
+}
+
+fn assert_registries(omni_chain: &mut OmniChain) {
+    let contract = omni_chain.get_dst_contract("ETHEREUM", "send_greeting");
+    assert_eq!(contract.contract_address, vec![0x01, 0x02]);
+    assert_eq!(contract.action_name, vec![0x03, 0x04]);
+    assert_eq!(omni_chain.get_registered_dst_contracts(0, 10).len(), 1);
+    assert_eq!(
+        omni_chain.get_permitted_contracts(0, 10),
+        vec![(
+            "ETHEREUM".to_string(),
+            vec![0xab, 0xcd],
+            "receive_greeting".to_string()
+        )]
+    );
+    omni_chain.assert_register_permitted_contract(
+        &"ETHEREUM".to_string(),
+        &vec![0xab, 0xcd],
+        &"receive_greeting".to_string(),
+    );
+    // The entries of the old collections are gone.
+    assert!(!env::storage_has_key(
+        &[b"dk".as_ref(), &0u64.to_le_bytes()].concat()
+    ));
+    assert!(!env::storage_has_key(
+        &[b"pk".as_ref(), &0u64.to_le_bytes()].concat()
+    ));
+}
+
+#[test]
+fn migrate_v0() {
+    let state = load_snapshot(V0_SNAPSHOT);
+    let old = OmniChainV0::try_from_slice(&state).unwrap();
+    assert_eq!(old.owner_id.as_str(), "owner.near");
+    assert_eq!(old.omni_chain_contract_id.as_str(), "omni.near");
+
+    let mut state = migrate(VersionedOmniChain::V0(old));
+    assert_eq!(state.version(), VersionedOmniChain::CURRENT_VERSION);
+    assert_eq!(state.owner_id.as_str(), "owner.near");
+    assert!(!state.outbound_log_enabled);
+    assert_eq!(state.max_send_attempts, 3);
+    assert_registries(&mut state);
+}
+
+#[test]
+fn migrate_untagged_contract_state() {
+    let state = load_snapshot(V0_SNAPSHOT);
+    let greeting = "hello".to_string().try_to_vec().unwrap();
+    env::storage_write(b"STATE", &[state, greeting].concat());
+
+    let mut contract = Greeting::migrate();
+    assert_eq!(contract.greeting, "hello");
+    assert_eq!(
+        contract.omni_chain.version(),
+        VersionedOmniChain::CURRENT_VERSION
+    );
+    assert_registries(&mut contract.omni_chain);
+
+    // Stored again, the state is read back tagged with the current version.
+    env::state_write(&contract);
+    let contract: Greeting = env::state_read().unwrap();
+    assert_eq!(
+        contract.omni_chain.version(),
+        VersionedOmniChain::CURRENT_VERSION
+    );
+    assert_eq!(contract.omni_chain.owner_id.as_str(), "owner.near");
+}
+
+#[test]
+fn current_state_round_trip() {
+    setup_context();
+    let omni_chain = OmniChain::new(
+        "owner.near".parse().unwrap(),
+        b"d".to_vec(),
+        b"p".to_vec(),
+        "omni.near".parse().unwrap(),
+    );
+    let state = VersionedOmniChain::from(omni_chain).try_to_vec().unwrap();
+    let state = migrate(VersionedOmniChain::try_from_slice(&state).unwrap());
+    assert_eq!(state.version(), VersionedOmniChain::CURRENT_VERSION);
+    assert_eq!(state.omni_chain_contract_id.as_str(), "omni.near");
+}
+
+#[test]
+#[should_panic(expected = "OmniChain state not migrated")]
+fn old_state_not_migrated() {
+    let state = load_snapshot(V0_SNAPSHOT);
+    let state = VersionedOmniChain::V0(OmniChainV0::try_from_slice(&state).unwrap());
+    state.get_dst_contract("ETHEREUM", "send_greeting");
+}
@@ -0,0 +1,141 @@
+//! Messages sent by `OmniChain`: routing, and the log filled by
+//! `OmniChain::on_message_sent`.
+
+use near_sdk::json_types::U64;
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::{testing_env, PromiseResult, RuntimeFeesConfig, VMConfig};
+use protocol_sdk::core_impl::RateLimit;
+use protocol_sdk::{Content, OmniChain, Payload, Value, U128};
+
+const OWNER: &str = "owner.near";
+
+fn set_predecessor(account_id: &str, promise_results: Vec<PromiseResult>) {
+    testing_env!(
+        VMContextBuilder::new()
+            .current_account_id("contract.near".parse().unwrap())
+            .predecessor_account_id(account_id.parse().unwrap())
+            .block_timestamp(1_000)
+            .build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        Default::default(),
+        promise_results,
+    );
+}
+
+fn content(contract: u8) -> Content {
+    Content {
+        contract: vec![contract],
+        action: vec![2],
+        data: Payload::new(),
+    }
+}
+
+/// Logs a message sent to `to_chain` and given `id` by the cross-chain contract.
+fn sent(omni_chain: &mut OmniChain, to_chain: &str, id: u128, contract: u8) {
+    set_predecessor(
+        "contract.near",
+        vec![PromiseResult::Successful(
+            near_sdk::serde_json::to_vec(&U128(id)).unwrap(),
+        )],
+    );
+    omni_chain.on_message_sent(
+        "alice.near".parse().unwrap(),
+        to_chain.to_string(),
+        content(contract),
+        None,
+        0,
+    );
+}
+
+fn omni_chain() -> OmniChain {
+    set_predecessor(OWNER, vec![]);
+    OmniChain::new(
+        OWNER.parse().unwrap(),
+        b"d".to_vec(),
+        b"p".to_vec(),
+        "omni.near".parse().unwrap(),
+    )
+}
+
+#[test]
+#[should_panic(expected = "exceeds the limit of 50 bytes for HUB")]
+fn check_envelope_against_first_hop() {
+    let mut omni_chain = omni_chain();
+    omni_chain.register_dst_route(
+        "SPOKE".to_string(),
+        vec![("HUB".to_string(), "0x01".to_string(), "0x02".to_string())],
+    );
+    omni_chain.set_max_payload_size("HUB".to_string(), Some(50));
+    let mut content = content(1);
+    content
+        .data
+        .set_item("data".to_string(), Value::Bytes(vec![0; 40]));
+    set_predecessor("alice.near", vec![]);
+    omni_chain.call_cross("SPOKE".to_string(), content);
+}
+
+#[test]
+fn log_same_id_on_each_chain() {
+    let mut omni_chain = omni_chain();
+    omni_chain.set_outbound_log(true, None);
+    sent(&mut omni_chain, "ETHEREUM", 7, 1);
+    sent(&mut omni_chain, "POLYGON", 7, 2);
+    let ethereum = omni_chain
+        .get_outbound_message("ETHEREUM".to_string(), U128(7))
+        .unwrap();
+    assert_eq!(ethereum.contract, vec![1]);
+    let polygon = omni_chain
+        .get_outbound_message("POLYGON".to_string(), U128(7))
+        .unwrap();
+    assert_eq!(polygon.contract, vec![2]);
+    let by_sender =
+        omni_chain.get_outbound_messages(None, Some("alice.near".parse().unwrap()), 0, 10);
+    assert_eq!(by_sender.len(), 2);
+    let by_chain = omni_chain.get_outbound_messages(Some("POLYGON".to_string()), None, 0, 10);
+    assert_eq!(by_chain.len(), 1);
+    assert_eq!(by_chain[0].1.contract, vec![2]);
+}
+
+#[test]
+#[should_panic(expected = "Rate limit of 1 messages to ETHEREUM exceeded for alice.near")]
+fn reject_messages_over_rate_limit() {
+    let mut omni_chain = omni_chain();
+    omni_chain.set_outbound_rate_limit(
+        "ETHEREUM".to_string(),
+        Some(RateLimit {
+            max_messages: 1,
+            window: U64(u64::MAX),
+        }),
+    );
+    set_predecessor("alice.near", vec![]);
+    omni_chain.call_cross("ETHEREUM".to_string(), content(1));
+    omni_chain.call_cross("ETHEREUM".to_string(), content(1));
+}
+
+#[test]
+fn reset_rate_usage_after_window() {
+    let mut omni_chain = omni_chain();
+    omni_chain.set_outbound_rate_limit(
+        "ETHEREUM".to_string(),
+        Some(RateLimit {
+            max_messages: 1,
+            window: U64(u64::MAX),
+        }),
+    );
+    set_predecessor("alice.near", vec![]);
+    omni_chain.call_cross("ETHEREUM".to_string(), content(1));
+    let key = ("alice.near".parse().unwrap(), "ETHEREUM".to_string());
+    assert_eq!(omni_chain.outbound_rate_usage.get(&key).unwrap().count, 1);
+    set_predecessor(OWNER, vec![]);
+    omni_chain.set_outbound_rate_limit(
+        "ETHEREUM".to_string(),
+        Some(RateLimit {
+            max_messages: 1,
+            window: U64(0),
+        }),
+    );
+    set_predecessor("alice.near", vec![]);
+    omni_chain.call_cross("ETHEREUM".to_string(), content(1));
+    assert_eq!(omni_chain.outbound_rate_usage.get(&key).unwrap().count, 1);
+}
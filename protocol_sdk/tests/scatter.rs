@@ -0,0 +1,138 @@
+//! Scatter-gather requests: `OmniChain::scatter_request`, `OmniChain::on_scatter_sent`
+//! and the responses collected by `OmniChain::accept_inbound_message`.
+
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::{testing_env, Gas, PromiseResult, RuntimeFeesConfig, VMConfig};
+use protocol_sdk::core_impl::GAS_FOR_SCATTER_CALLBACK;
+use protocol_sdk::{Context, OmniChain, Payload, Session, U128};
+
+const OWNER: &str = "owner.near";
+const CROSS_CHAIN: &str = "omni.near";
+const CHAINS: [&str; 3] = ["ETHEREUM", "POLYGON", "AVALANCHE"];
+
+fn set_context(account_id: &str, prepaid_gas: Gas, promise_results: Vec<PromiseResult>) {
+    testing_env!(
+        VMContextBuilder::new()
+            .current_account_id("contract.near".parse().unwrap())
+            .predecessor_account_id(account_id.parse().unwrap())
+            .block_timestamp(1_000)
+            .prepaid_gas(prepaid_gas)
+            .build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        Default::default(),
+        promise_results,
+    );
+}
+
+/// An `OmniChain` accepting any message of `0xabcd`, with a scatter request to `CHAINS`
+/// stored under correlation id 0 as `scatter_request` leaves it.
+fn omni_chain() -> OmniChain {
+    set_context(OWNER, Gas(300_000_000_000_000), vec![]);
+    let mut omni_chain = OmniChain::new(
+        OWNER.parse().unwrap(),
+        b"d".to_vec(),
+        b"p".to_vec(),
+        CROSS_CHAIN.parse().unwrap(),
+    );
+    omni_chain.add_permission_rule(None, Some("0xabcd".to_string()), None, true);
+    for chain in CHAINS {
+        omni_chain.register_dst_contract(
+            chain.to_string(),
+            "price".to_string(),
+            "0xabcd".to_string(),
+            "0x7072696365".to_string(),
+        );
+    }
+    omni_chain.scatter_request(
+        CHAINS.iter().map(|chain| chain.to_string()).collect(),
+        "price".to_string(),
+        Payload::new(),
+        "on_prices".to_string(),
+        None,
+        100,
+    );
+    omni_chain
+}
+
+fn sent(id: u128) -> PromiseResult {
+    PromiseResult::Successful(near_sdk::serde_json::to_vec(&Some(U128(id))).unwrap())
+}
+
+fn response(chain: &str, id: u128) -> Context {
+    Context {
+        id: U128(1),
+        from_chain: chain.to_string(),
+        sender: vec![0xab, 0xcd],
+        signer: vec![],
+        contract_id: "contract.near".to_string(),
+        action: "on_prices".to_string(),
+        sqos: vec![],
+        session: Session {
+            id: U128(id),
+            session_type: Session::RESPONSE,
+            callback: None,
+            commitment: None,
+            answer: None,
+        },
+    }
+}
+
+#[test]
+fn complete_unsent_requests_within_scatter_callback_gas() {
+    let mut omni_chain = omni_chain();
+    set_context(
+        "contract.near",
+        GAS_FOR_SCATTER_CALLBACK,
+        CHAINS.iter().map(|_| PromiseResult::Failed).collect(),
+    );
+    omni_chain.on_scatter_sent(0, CHAINS.iter().map(|chain| chain.to_string()).collect());
+    assert!(omni_chain.gather.get(&0).is_none());
+}
+
+#[test]
+fn collect_responses_with_same_id_from_each_chain() {
+    let mut omni_chain = omni_chain();
+    set_context(
+        "contract.near",
+        GAS_FOR_SCATTER_CALLBACK,
+        CHAINS.iter().map(|_| sent(5)).collect(),
+    );
+    omni_chain.on_scatter_sent(0, CHAINS.iter().map(|chain| chain.to_string()).collect());
+    set_context(CROSS_CHAIN, Gas(300_000_000_000_000), vec![]);
+    for chain in &CHAINS[..2] {
+        let messages = omni_chain.accept_inbound_message(Payload::new(), response(chain, 5));
+        assert!(messages.is_empty());
+    }
+    assert_eq!(omni_chain.gather.get(&0).unwrap().responses.len(), 2);
+    omni_chain.accept_inbound_message(Payload::new(), response("AVALANCHE", 5));
+    assert!(omni_chain.gather.get(&0).is_none());
+}
+
+#[test]
+#[should_panic(expected = "Duplicate chain ETHEREUM")]
+fn reject_duplicate_chain() {
+    let mut omni_chain = omni_chain();
+    omni_chain.scatter_request(
+        vec!["ETHEREUM".to_string(), "ETHEREUM".to_string()],
+        "price".to_string(),
+        Payload::new(),
+        "on_prices".to_string(),
+        Some(1),
+        100,
+    );
+}
+
+#[test]
+#[should_panic(expected = "Invalid timeout")]
+fn reject_overflowing_timeout() {
+    let mut omni_chain = omni_chain();
+    omni_chain.scatter_request(
+        vec!["ETHEREUM".to_string()],
+        "price".to_string(),
+        Payload::new(),
+        "on_prices".to_string(),
+        None,
+        u64::MAX,
+    );
+}
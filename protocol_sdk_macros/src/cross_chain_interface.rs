@@ -0,0 +1,141 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{
+    Attribute, AttributeArgs, Error, FnArg, ItemTrait, Lit, LitStr, Meta, NestedMeta, Pat,
+    ReturnType, TraitItem, TraitItemMethod,
+};
+
+pub fn expand(args: AttributeArgs, item: ItemTrait) -> syn::Result<TokenStream> {
+    let mod_name =
+        match args.as_slice() {
+            [] => format_ident!("{}", to_snake_case(&item.ident.to_string())),
+            [NestedMeta::Meta(Meta::Path(path))] if path.get_ident().is_some() => {
+                path.get_ident().unwrap().clone()
+            }
+            _ => return Err(Error::new(
+                Span::call_site(),
+                "expected `#[cross_chain_interface]` or `#[cross_chain_interface(module_name)]`",
+            )),
+        };
+
+    let mut senders = Vec::new();
+    for trait_item in item.items.iter() {
+        match trait_item {
+            TraitItem::Method(method) => senders.push(expand_method(method)?),
+            _ => {
+                return Err(Error::new_spanned(
+                    trait_item,
+                    "cross chain interfaces may only contain methods",
+                ))
+            }
+        }
+    }
+
+    let vis = &item.vis;
+    Ok(quote! {
+        #vis mod #mod_name {
+            use super::*;
+            #(#senders)*
+        }
+    })
+}
+
+fn expand_method(method: &TraitItemMethod) -> syn::Result<TokenStream> {
+    let sig = &method.sig;
+    let name = &sig.ident;
+    let action = name.to_string();
+    let docs = method.attrs.iter().filter(|attr| attr.path.is_ident("doc"));
+
+    let mut arg_names = Vec::new();
+    let mut arg_types = Vec::new();
+    for input in sig.inputs.iter() {
+        if let FnArg::Typed(pat_type) = input {
+            match pat_type.pat.as_ref() {
+                Pat::Ident(pat) => arg_names.push(pat.ident.clone()),
+                pat => return Err(Error::new_spanned(pat, "expected an identifier")),
+            }
+            arg_types.push(pat_type.ty.as_ref().clone());
+        }
+    }
+    let item_names = arg_names.iter().map(|arg_name| arg_name.to_string());
+    let payload_mut = if arg_names.is_empty() {
+        quote! {}
+    } else {
+        quote! { mut }
+    };
+
+    let call = match (&sig.output, parse_callback(&method.attrs)?) {
+        (ReturnType::Default, None) => quote! {
+            omni_chain.call_cross(to_chain, __content)
+        },
+        (ReturnType::Type(..), Some(callback)) => quote! {
+            omni_chain.call_cross_with_session(to_chain, __content, #callback.as_bytes().to_vec())
+        },
+        (ReturnType::Default, Some(callback)) => {
+            return Err(Error::new_spanned(
+                callback,
+                "a callback is only used by methods with a return type",
+            ))
+        }
+        (output, None) => {
+            return Err(Error::new_spanned(
+                output,
+                "methods with a return type need `#[callback = \"...\"]`",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        #(#docs)*
+        pub fn #name(
+            omni_chain: &mut ::protocol_sdk::OmniChain,
+            to_chain: String,
+            #(#arg_names: #arg_types),*
+        ) -> ::near_sdk::Promise {
+            let #payload_mut __payload = ::protocol_sdk::Payload::new();
+            #(__payload.set_item(#item_names.to_string(), ::protocol_sdk::Value::from(#arg_names));)*
+            let __contract = omni_chain.get_dst_contract(&to_chain, #action);
+            let __content = ::protocol_sdk::Content {
+                contract: __contract.contract_address,
+                action: __contract.action_name,
+                data: __payload,
+            };
+            #call
+        }
+    })
+}
+
+fn parse_callback(attrs: &[Attribute]) -> syn::Result<Option<LitStr>> {
+    let mut callback = None;
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("callback")) {
+        match attr.parse_meta()? {
+            Meta::NameValue(meta) => match meta.lit {
+                Lit::Str(lit) if callback.is_none() => callback = Some(lit),
+                Lit::Str(lit) => return Err(Error::new_spanned(lit, "duplicate callback")),
+                lit => return Err(Error::new_spanned(lit, "expected a string literal")),
+            },
+            meta => {
+                return Err(Error::new_spanned(
+                    meta,
+                    "expected `#[callback = \"method_name\"]`",
+                ))
+            }
+        }
+    }
+    Ok(callback)
+}
+
+fn to_snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, ch) in name.char_indices() {
+        if ch.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.extend(ch.to_lowercase());
+        } else {
+            snake.push(ch);
+        }
+    }
+    snake
+}
@@ -0,0 +1,35 @@
+extern crate proc_macro;
+
+mod cross_chain_interface;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, AttributeArgs, ItemTrait};
+
+/// Generates typed senders for the actions of a contract on another chain.
+///
+/// Every method of the trait becomes a function in a module named after the trait
+/// (snake case), or after the identifier given as argument. The function takes the
+/// `OmniChain` of the calling contract and the destination chain, followed by the
+/// method arguments, and returns the `Promise` of the cross-chain call.
+///
+/// The method name is the action name used with `register_dst_contract`. Methods
+/// declaring a return type expect a response and must name the local method that
+/// receives it with `#[callback = "..."]`.
+///
+/// ```ignore
+/// #[cross_chain_interface(ext_compute)]
+/// pub trait ComputeService {
+///     #[callback = "receive_compute_result"]
+///     fn receive_compute_task(&self, nums: Vec<u32>) -> u32;
+/// }
+///
+/// ext_compute::receive_compute_task(&mut self.omni_chain, to_chain, nums);
+/// ```
+#[proc_macro_attribute]
+pub fn cross_chain_interface(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as AttributeArgs);
+    let item = parse_macro_input!(item as ItemTrait);
+    cross_chain_interface::expand(args, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,295 @@
+use crate::json_types::U128;
+use alloc::string::{String, ToString};
+use borsh::maybestd::io;
+use borsh::{BorshDeserialize, BorshSerialize};
+use core::convert::TryFrom;
+use core::fmt;
+use core::str::FromStr;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+pub use self::u256::U256;
+
+// The expansion of `construct_uint!` doesn't pass clippy.
+#[allow(clippy::all)]
+mod u256 {
+    uint::construct_uint! {
+        /// 256-bit unsigned integer, the `uint256` of EVM chains. Serialized to JSON as a
+        /// decimal string.
+        pub struct U256(4);
+    }
+}
+
+/// 256-bit signed integer in two's complement, the `int256` of EVM chains. Serialized to
+/// JSON as a decimal string.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct I256(U256);
+
+impl U256 {
+    pub fn to_be_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        self.to_big_endian(&mut bytes);
+        bytes
+    }
+
+    pub fn from_be_bytes(bytes: [u8; 32]) -> Self {
+        U256::from_big_endian(&bytes)
+    }
+}
+
+impl I256 {
+    pub const MAX: I256 = I256(U256([u64::MAX, u64::MAX, u64::MAX, u64::MAX >> 1]));
+    pub const MIN: I256 = I256(U256([0, 0, 0, 1 << 63]));
+
+    pub fn is_negative(&self) -> bool {
+        self.0.bit(255)
+    }
+
+    /// The absolute value, which always fits as `I256::MIN` is `-2^255`.
+    pub fn unsigned_abs(&self) -> U256 {
+        if self.is_negative() {
+            self.0.overflowing_neg().0
+        } else {
+            self.0
+        }
+    }
+
+    pub fn to_be_bytes(&self) -> [u8; 32] {
+        self.0.to_be_bytes()
+    }
+
+    pub fn from_be_bytes(bytes: [u8; 32]) -> Self {
+        I256(U256::from_be_bytes(bytes))
+    }
+}
+
+impl From<U128> for U256 {
+    fn from(value: U128) -> Self {
+        U256::from(value.0)
+    }
+}
+
+impl TryFrom<U256> for U128 {
+    type Error = &'static str;
+
+    fn try_from(value: U256) -> Result<Self, Self::Error> {
+        u128::try_from(value).map(U128)
+    }
+}
+
+impl From<i128> for I256 {
+    fn from(value: i128) -> Self {
+        if value < 0 {
+            // !value is -value - 1, which is not negative; flipping back sign-extends.
+            I256(!U256::from(!value as u128))
+        } else {
+            I256(U256::from(value as u128))
+        }
+    }
+}
+
+impl TryFrom<I256> for i128 {
+    type Error = &'static str;
+
+    fn try_from(value: I256) -> Result<Self, Self::Error> {
+        let err_str = "integer overflow when casting to i128";
+        if value.is_negative() {
+            let inverted = u128::try_from(!value.0).map_err(|_| err_str)?;
+            if inverted > i128::MAX as u128 {
+                return Err(err_str);
+            }
+            Ok(!(inverted as i128))
+        } else {
+            let positive = u128::try_from(value.0).map_err(|_| err_str)?;
+            if positive > i128::MAX as u128 {
+                return Err(err_str);
+            }
+            Ok(positive as i128)
+        }
+    }
+}
+
+impl fmt::Display for I256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_negative() {
+            write!(f, "-{}", self.unsigned_abs())
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+impl fmt::Debug for I256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl FromStr for I256 {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err_str = "invalid int256";
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s),
+        };
+        // `from_dec_str` reads no digits as zero.
+        if digits.is_empty() {
+            return Err(err_str);
+        }
+        let abs = U256::from_dec_str(digits).map_err(|_| err_str)?;
+        if negative {
+            if abs > I256::MIN.0 {
+                return Err(err_str);
+            }
+            Ok(I256(abs.overflowing_neg().0))
+        } else {
+            if abs > I256::MAX.0 {
+                return Err(err_str);
+            }
+            Ok(I256(abs))
+        }
+    }
+}
+
+impl BorshSerialize for U256 {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut bytes = [0u8; 32];
+        self.to_little_endian(&mut bytes);
+        writer.write_all(&bytes)
+    }
+}
+
+impl BorshDeserialize for U256 {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        let bytes: [u8; 32] = BorshDeserialize::deserialize(buf)?;
+        Ok(U256::from_little_endian(&bytes))
+    }
+}
+
+impl BorshSerialize for I256 {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        BorshSerialize::serialize(&self.0, writer)
+    }
+}
+
+impl BorshDeserialize for I256 {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        Ok(I256(BorshDeserialize::deserialize(buf)?))
+    }
+}
+
+impl Serialize for U256 {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for U256 {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = <String as Deserialize>::deserialize(deserializer)?;
+        if s.is_empty() {
+            return Err(de::Error::custom("invalid uint256"));
+        }
+        U256::from_dec_str(&s).map_err(|err| de::Error::custom(err.to_string()))
+    }
+}
+
+impl Serialize for I256 {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for I256 {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = <String as Deserialize>::deserialize(deserializer)?;
+        I256::from_str(&s).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+    use serde::de::value::{Error as ValueError, StrDeserializer};
+    use serde::de::IntoDeserializer;
+
+    fn deserialize_u256(s: &str) -> Result<U256, ValueError> {
+        let deserializer: StrDeserializer<ValueError> = s.into_deserializer();
+        <U256 as Deserialize>::deserialize(deserializer)
+    }
+
+    fn deserialize_i256(s: &str) -> Result<I256, ValueError> {
+        let deserializer: StrDeserializer<ValueError> = s.into_deserializer();
+        <I256 as Deserialize>::deserialize(deserializer)
+    }
+
+    #[test]
+    fn parse_u256() {
+        assert_eq!(deserialize_u256("123").unwrap(), U256::from(123));
+        assert!(deserialize_u256("").is_err());
+        assert!(deserialize_u256("-1").is_err());
+        assert!(deserialize_u256("12a").is_err());
+    }
+
+    #[test]
+    fn parse_i256() {
+        assert_eq!(I256::from_str("-123").unwrap(), I256::from(-123));
+        assert_eq!(I256::from_str("0").unwrap(), I256::default());
+        assert_eq!(deserialize_i256("42").unwrap(), I256::from(42));
+        assert!(I256::from_str("").is_err());
+        assert!(I256::from_str("-").is_err());
+        assert!(deserialize_i256("").is_err());
+        assert!(deserialize_i256("-").is_err());
+    }
+
+    #[test]
+    fn parse_i256_bounds() {
+        assert_eq!(I256::from_str(&I256::MIN.to_string()).unwrap(), I256::MIN);
+        assert_eq!(I256::from_str(&I256::MAX.to_string()).unwrap(), I256::MAX);
+        let over_max = (I256::MAX.0 + U256::one()).to_string();
+        assert!(I256::from_str(&over_max).is_err());
+        assert!(I256::from_str(&format!("-{}", over_max)).is_ok());
+        assert!(I256::from_str(&format!("-{}", over_max + "0")).is_err());
+    }
+
+    #[test]
+    fn i128_conversions() {
+        for value in [i128::MIN, i128::MIN + 1, -1, 0, 1, i128::MAX] {
+            assert_eq!(i128::try_from(I256::from(value)).unwrap(), value);
+            assert_eq!(I256::from(value).to_string(), value.to_string());
+        }
+        assert!(i128::try_from(I256::MIN).is_err());
+        assert!(i128::try_from(I256::MAX).is_err());
+        let below_min = I256(I256::from(i128::MIN).0.overflowing_sub(U256::one()).0);
+        assert!(i128::try_from(below_min).is_err());
+        assert_eq!(I256::MIN.unsigned_abs(), U256::one() << 255);
+        assert_eq!(I256::from(i128::MIN).unsigned_abs(), U256::one() << 127);
+    }
+
+    #[test]
+    fn be_bytes() {
+        let mut one = [0u8; 32];
+        one[31] = 1;
+        assert_eq!(U256::one().to_be_bytes(), one);
+        assert_eq!(U256::from_be_bytes(one), U256::one());
+        assert_eq!(I256::from(-1).to_be_bytes(), [0xff; 32]);
+        let mut min = [0u8; 32];
+        min[0] = 0x80;
+        assert_eq!(I256::MIN.to_be_bytes(), min);
+        assert_eq!(I256::from_be_bytes(min), I256::MIN);
+    }
+}
@@ -0,0 +1,208 @@
+use crate::error::Error;
+use crate::hasher::Hasher;
+use crate::types::{MessageItem, Payload, Value};
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use borsh::{BorshDeserialize, BorshSerialize};
+use core::convert::TryInto;
+
+/// Name of the item carrying a fragment, reserved by chunked transfers.
+pub const FRAGMENT_ITEM: &str = "__fragment";
+
+/// A piece of a payload too large to be sent in one message. The receiver collects the
+/// fragments of a transfer, joins their data in order, and checks it against `hash`, the
+/// SHA-256 of the Borsh encoding of the full payload.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Fragment {
+    pub transfer_id: [u8; 32],
+    pub index: u32,
+    pub total: u32,
+    pub hash: [u8; 32],
+    pub data: Vec<u8>,
+}
+
+impl Fragment {
+    /// Splits the encoding of a payload into fragments of at most `chunk_size` bytes.
+    pub fn split<H: Hasher>(
+        payload: &Payload,
+        transfer_id: [u8; 32],
+        chunk_size: usize,
+    ) -> Vec<Fragment> {
+        assert!(chunk_size > 0, "chunk size must be positive");
+        let encoded = payload.try_to_vec().unwrap();
+        let hash = hash_of::<H>(&encoded);
+        let chunks: Vec<&[u8]> = encoded.chunks(chunk_size).collect();
+        let total = chunks.len() as u32;
+        chunks
+            .into_iter()
+            .enumerate()
+            .map(|(index, data)| Fragment {
+                transfer_id,
+                index: index as u32,
+                total,
+                hash,
+                data: data.to_vec(),
+            })
+            .collect()
+    }
+
+    /// Joins the data of all fragments of a transfer, ordered by index, back into the payload.
+    pub fn join<H: Hasher>(hash: &[u8; 32], data: Vec<Vec<u8>>) -> Result<Payload, Error> {
+        let encoded = data.concat();
+        if hash_of::<H>(&encoded) != *hash {
+            return Err(Error::FragmentHashMismatch);
+        }
+        Payload::try_from_slice(&encoded).map_err(|_| Error::InvalidFragment)
+    }
+
+    /// Reads the fragment carried by a payload, `None` if it is a regular payload.
+    pub fn from_payload(payload: &Payload) -> Result<Option<Fragment>, Error> {
+        if !payload.contains(FRAGMENT_ITEM) {
+            return Ok(None);
+        }
+        let fields = payload
+            .get::<Vec<MessageItem>>(FRAGMENT_ITEM)
+            .map_err(|_| Error::InvalidFragment)?;
+        match fields.as_slice() {
+            [transfer_id, index, total, hash, data] => {
+                let fragment = Fragment {
+                    transfer_id: field(transfer_id, "transfer_id")?,
+                    index: field(index, "index")?,
+                    total: field(total, "total")?,
+                    hash: field(hash, "hash")?,
+                    data: match (data.name.as_str(), &data.value) {
+                        ("data", Value::Bytes(data)) => data.clone(),
+                        _ => return Err(Error::InvalidFragment),
+                    },
+                };
+                if fragment.index >= fragment.total {
+                    return Err(Error::InvalidFragment);
+                }
+                Ok(Some(fragment))
+            }
+            _ => Err(Error::InvalidFragment),
+        }
+    }
+
+    pub fn into_payload(self) -> Payload {
+        let fields = vec![
+            item("transfer_id", Value::Bytes32(self.transfer_id)),
+            item("index", Value::Uint32(self.index)),
+            item("total", Value::Uint32(self.total)),
+            item("hash", Value::Bytes32(self.hash)),
+            item("data", Value::Bytes(self.data)),
+        ];
+        let mut payload = Payload::new();
+        payload.set_item(FRAGMENT_ITEM.to_string(), Value::Struct(fields));
+        payload
+    }
+}
+
+fn hash_of<H: Hasher>(data: &[u8]) -> [u8; 32] {
+    H::sha256(data)
+        .as_slice()
+        .try_into()
+        .expect("sha256 is 32 bytes")
+}
+
+fn item(name: &str, value: Value) -> MessageItem {
+    MessageItem {
+        name: name.to_string(),
+        value,
+    }
+}
+
+fn field<T: TryFrom<Value>>(item: &MessageItem, name: &str) -> Result<T, Error> {
+    if item.name != name {
+        return Err(Error::InvalidFragment);
+    }
+    T::try_from(item.value.clone()).map_err(|_| Error::InvalidFragment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+    use sha2::Digest;
+
+    struct TestHasher;
+
+    impl Hasher for TestHasher {
+        fn sha256(data: &[u8]) -> Vec<u8> {
+            sha2::Sha256::digest(data).to_vec()
+        }
+    }
+
+    fn payload() -> Payload {
+        vec![
+            (
+                String::from("title"),
+                Value::String(String::from("greeting")),
+            ),
+            (String::from("nums"), Value::VecUint32(vec![1, 2, 3, 4, 5])),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    #[test]
+    fn split_join_round_trip() {
+        let payload = payload();
+        let fragments = Fragment::split::<TestHasher>(&payload, [7; 32], 7);
+        let encoded_size = payload.try_to_vec().unwrap().len();
+        assert_eq!(fragments.len(), encoded_size.div_ceil(7));
+        let data = fragments
+            .into_iter()
+            .enumerate()
+            .map(|(index, fragment)| {
+                let fragment = Fragment::from_payload(&fragment.into_payload())
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(fragment.index as usize, index);
+                assert_eq!(fragment.transfer_id, [7; 32]);
+                fragment
+            })
+            .collect::<Vec<_>>();
+        let hash = data[0].hash;
+        let data = data.into_iter().map(|fragment| fragment.data).collect();
+        assert_eq!(Fragment::join::<TestHasher>(&hash, data).unwrap(), payload);
+    }
+
+    #[test]
+    fn single_fragment() {
+        let fragments = Fragment::split::<TestHasher>(&payload(), [0; 32], 4096);
+        assert_eq!(fragments.len(), 1);
+        assert_eq!(fragments[0].total, 1);
+    }
+
+    #[test]
+    fn regular_payload() {
+        assert_eq!(Fragment::from_payload(&payload()), Ok(None));
+    }
+
+    #[test]
+    fn index_out_of_range() {
+        let mut fragment = Fragment::split::<TestHasher>(&payload(), [0; 32], 7).remove(0);
+        fragment.index = fragment.total;
+        assert_eq!(
+            Fragment::from_payload(&fragment.into_payload()),
+            Err(Error::InvalidFragment)
+        );
+    }
+
+    #[test]
+    fn hash_mismatch() {
+        let fragments = Fragment::split::<TestHasher>(&payload(), [0; 32], 7);
+        let hash = fragments[0].hash;
+        let mut data: Vec<Vec<u8>> = fragments
+            .into_iter()
+            .map(|fragment| fragment.data)
+            .collect();
+        data.swap(0, 1);
+        assert_eq!(
+            Fragment::join::<TestHasher>(&hash, data),
+            Err(Error::FragmentHashMismatch)
+        );
+    }
+}
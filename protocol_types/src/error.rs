@@ -0,0 +1,70 @@
+use crate::schema::ValueKind;
+use alloc::string::String;
+use core::fmt;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Error {
+    DuplicateItem(String),
+    MissingItem(String),
+    UnexpectedItem(String),
+    TypeMismatch {
+        name: String,
+        expected: ValueKind,
+        found: ValueKind,
+    },
+    InvalidValue {
+        expected: ValueKind,
+        found: ValueKind,
+    },
+    InvalidAccountId(String),
+    InvalidFragment,
+    FragmentHashMismatch,
+    InvalidEnvelope,
+    ReservedItem(String),
+    InvalidSchema(String),
+    LengthMismatch {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::DuplicateItem(name) => write!(f, "duplicate item {}", name),
+            Error::MissingItem(name) => write!(f, "missing item {}", name),
+            Error::UnexpectedItem(name) => write!(f, "unexpected item {}", name),
+            Error::TypeMismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "item {} expected {:?}, found {:?}",
+                name, expected, found
+            ),
+            Error::InvalidValue { expected, found } => {
+                write!(f, "expected {:?}, found {:?}", expected, found)
+            }
+            Error::InvalidAccountId(account_id) => write!(f, "invalid account id {}", account_id),
+            Error::InvalidFragment => write!(f, "invalid fragment"),
+            Error::FragmentHashMismatch => write!(f, "fragments don't match the payload hash"),
+            Error::InvalidEnvelope => write!(f, "invalid forwarding envelope"),
+            Error::ReservedItem(name) => write!(f, "item name {} is reserved", name),
+            Error::InvalidSchema(name) => write!(f, "invalid schema of item {}", name),
+            Error::LengthMismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "item {} expected {} elements, found {}",
+                name, expected, found
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
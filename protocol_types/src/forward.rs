@@ -0,0 +1,56 @@
+use crate::error::Error;
+use crate::types::{Content, MessageItem, Payload, Value};
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Name of the item carrying a forwarding envelope, reserved by routed messages.
+pub const FORWARD_ITEM: &str = "__forward";
+
+/// A message for a chain which is not reachable directly, sent to a forwarder on an
+/// intermediate chain which sends `content` on to `to_chain`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Envelope {
+    pub to_chain: String,
+    pub content: Content,
+}
+
+impl Envelope {
+    /// Reads the envelope carried by a payload, `None` if it is a regular payload.
+    pub fn from_payload(payload: &Payload) -> Result<Option<Envelope>, Error> {
+        if !payload.contains(FORWARD_ITEM) {
+            return Ok(None);
+        }
+        let fields = payload
+            .get::<Vec<MessageItem>>(FORWARD_ITEM)
+            .map_err(|_| Error::InvalidEnvelope)?;
+        match fields.as_slice() {
+            [to_chain, content] => match (&to_chain.value, &content.value) {
+                (Value::String(to_chain), Value::Bytes(content)) => Ok(Some(Envelope {
+                    to_chain: to_chain.clone(),
+                    content: Content::try_from_slice(content)
+                        .map_err(|_| Error::InvalidEnvelope)?,
+                })),
+                _ => Err(Error::InvalidEnvelope),
+            },
+            _ => Err(Error::InvalidEnvelope),
+        }
+    }
+
+    pub fn into_payload(self) -> Payload {
+        let fields = vec![
+            MessageItem {
+                name: "to_chain".to_string(),
+                value: Value::String(self.to_chain),
+            },
+            MessageItem {
+                name: "content".to_string(),
+                value: Value::Bytes(self.content.try_to_vec().unwrap()),
+            },
+        ];
+        let mut payload = Payload::new();
+        payload.set_item(FORWARD_ITEM.to_string(), Value::Struct(fields));
+        payload
+    }
+}
@@ -0,0 +1,19 @@
+use alloc::vec::Vec;
+
+/// The hash function used to identify messages. Contracts hash with the runtime host
+/// function, off-chain clients can plug in any SHA-256 implementation.
+pub trait Hasher {
+    fn sha256(data: &[u8]) -> Vec<u8>;
+}
+
+/// SHA-256 computed by the `sha2` crate, for use outside of a contract.
+#[cfg(feature = "sha2")]
+pub struct Sha2Hasher;
+
+#[cfg(feature = "sha2")]
+impl Hasher for Sha2Hasher {
+    fn sha256(data: &[u8]) -> Vec<u8> {
+        use sha2::Digest;
+        sha2::Sha256::digest(data).to_vec()
+    }
+}
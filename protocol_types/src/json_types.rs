@@ -0,0 +1,54 @@
+use alloc::string::{String, ToString};
+use borsh::{BorshDeserialize, BorshSerialize};
+use core::str::FromStr;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A `u128` that is serialized to JSON as a decimal string, wire compatible with
+/// `near_sdk::json_types::U128`.
+#[derive(
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    BorshDeserialize,
+    BorshSerialize,
+    Debug,
+)]
+pub struct U128(pub u128);
+
+impl From<u128> for U128 {
+    fn from(v: u128) -> Self {
+        U128(v)
+    }
+}
+
+impl From<U128> for u128 {
+    fn from(v: U128) -> u128 {
+        v.0
+    }
+}
+
+impl Serialize for U128 {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.0.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for U128 {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = <String as Deserialize>::deserialize(deserializer)?;
+        u128::from_str(&s)
+            .map(U128)
+            .map_err(|err| de::Error::custom(err.to_string()))
+    }
+}
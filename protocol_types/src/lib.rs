@@ -0,0 +1,25 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod big_int;
+mod chunk;
+mod error;
+mod forward;
+pub mod hasher;
+pub mod json_types;
+#[cfg(feature = "near-sdk")]
+mod near;
+mod schema;
+mod types;
+
+pub use self::big_int::{I256, U256};
+pub use self::chunk::{Fragment, FRAGMENT_ITEM};
+pub use self::error::Error;
+pub use self::forward::{Envelope, FORWARD_ITEM};
+pub use self::hasher::Hasher;
+#[cfg(feature = "sha2")]
+pub use self::hasher::Sha2Hasher;
+pub use self::json_types::U128;
+pub use self::schema::*;
+pub use self::types::*;
@@ -0,0 +1,68 @@
+//! Conversions between `Value` and the JSON types of `near-sdk`, and between the
+//! `U128` of this crate and the one of `near-sdk`.
+use crate::error::Error;
+use crate::json_types;
+use crate::schema::ValueKind;
+use crate::types::Value;
+use alloc::string::ToString;
+use core::convert::TryFrom;
+use near_sdk::json_types::{U128, U64};
+use near_sdk::AccountId;
+
+impl From<U128> for json_types::U128 {
+    fn from(value: U128) -> Self {
+        json_types::U128(value.0)
+    }
+}
+
+impl From<json_types::U128> for U128 {
+    fn from(value: json_types::U128) -> Self {
+        U128(value.0)
+    }
+}
+
+impl From<U128> for Value {
+    fn from(value: U128) -> Self {
+        Value::Uint128(value.0)
+    }
+}
+
+impl TryFrom<Value> for U128 {
+    type Error = Error;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        u128::try_from(value).map(U128)
+    }
+}
+
+impl From<U64> for Value {
+    fn from(value: U64) -> Self {
+        Value::Uint64(value.0)
+    }
+}
+
+impl TryFrom<Value> for U64 {
+    type Error = Error;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        u64::try_from(value).map(U64)
+    }
+}
+
+impl From<AccountId> for Value {
+    fn from(value: AccountId) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+impl TryFrom<Value> for AccountId {
+    type Error = Error;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::String(account_id) => AccountId::try_from(account_id.clone())
+                .map_err(|_| Error::InvalidAccountId(account_id)),
+            value => Err(Error::InvalidValue {
+                expected: ValueKind::String,
+                found: value.kind(),
+            }),
+        }
+    }
+}
@@ -0,0 +1,445 @@
+use crate::error::Error;
+use crate::types::{MessageItem, Payload, Value};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use borsh::maybestd::io;
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+/// The variant of a `Value`, without its content.
+#[derive(
+    Clone, Copy, PartialEq, Eq, BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug,
+)]
+pub enum ValueKind {
+    String,
+    Uint8,
+    Uint16,
+    Uint32,
+    Uint64,
+    Uint128,
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+    VecString,
+    VecUint8,
+    VecUint16,
+    VecUint32,
+    VecUint64,
+    VecUint128,
+    VecInt8,
+    VecInt16,
+    VecInt32,
+    VecInt64,
+    Address,
+    Bool,
+    Bytes,
+    Bytes32,
+    Int128,
+    Option,
+    Tuple,
+    Struct,
+    Array,
+    Uint256,
+    Int256,
+}
+
+impl Value {
+    pub fn kind(&self) -> ValueKind {
+        match self {
+            Value::String(_) => ValueKind::String,
+            Value::Uint8(_) => ValueKind::Uint8,
+            Value::Uint16(_) => ValueKind::Uint16,
+            Value::Uint32(_) => ValueKind::Uint32,
+            Value::Uint64(_) => ValueKind::Uint64,
+            Value::Uint128(_) => ValueKind::Uint128,
+            Value::Int8(_) => ValueKind::Int8,
+            Value::Int16(_) => ValueKind::Int16,
+            Value::Int32(_) => ValueKind::Int32,
+            Value::Int64(_) => ValueKind::Int64,
+            Value::VecString(_) => ValueKind::VecString,
+            Value::VecUint8(_) => ValueKind::VecUint8,
+            Value::VecUint16(_) => ValueKind::VecUint16,
+            Value::VecUint32(_) => ValueKind::VecUint32,
+            Value::VecUint64(_) => ValueKind::VecUint64,
+            Value::VecUint128(_) => ValueKind::VecUint128,
+            Value::VecInt8(_) => ValueKind::VecInt8,
+            Value::VecInt16(_) => ValueKind::VecInt16,
+            Value::VecInt32(_) => ValueKind::VecInt32,
+            Value::VecInt64(_) => ValueKind::VecInt64,
+            Value::Address(_) => ValueKind::Address,
+            Value::Bool(_) => ValueKind::Bool,
+            Value::Bytes(_) => ValueKind::Bytes,
+            Value::Bytes32(_) => ValueKind::Bytes32,
+            Value::Int128(_) => ValueKind::Int128,
+            Value::Option(_) => ValueKind::Option,
+            Value::Tuple(_) => ValueKind::Tuple,
+            Value::Struct(_) => ValueKind::Struct,
+            Value::Array(_) => ValueKind::Array,
+            Value::Uint256(_) => ValueKind::Uint256,
+            Value::Int256(_) => ValueKind::Int256,
+        }
+    }
+}
+
+/// The expected type of an item. `fields` declares the contents of compound values: the
+/// fields of a `Struct`, the elements of a `Tuple` in order, or the single element type of
+/// an `Option` or an `Array`, whose name is only used in errors.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct ItemSchema {
+    pub name: String,
+    pub kind: ValueKind,
+    pub required: bool,
+    #[serde(default)]
+    pub fields: Vec<ItemSchema>,
+}
+
+// Written by hand, the derive can't resolve the bounds of the recursive `fields`.
+impl BorshSerialize for ItemSchema {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        BorshSerialize::serialize(&self.name, writer)?;
+        BorshSerialize::serialize(&self.kind, writer)?;
+        BorshSerialize::serialize(&self.required, writer)?;
+        BorshSerialize::serialize(&self.fields, writer)
+    }
+}
+
+impl BorshDeserialize for ItemSchema {
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        Ok(ItemSchema {
+            name: BorshDeserialize::deserialize(buf)?,
+            kind: BorshDeserialize::deserialize(buf)?,
+            required: BorshDeserialize::deserialize(buf)?,
+            fields: BorshDeserialize::deserialize(buf)?,
+        })
+    }
+}
+
+impl ItemSchema {
+    fn check(&self, path: &str) -> Result<(), Error> {
+        let valid = match self.kind {
+            ValueKind::Struct => {
+                check_items(&self.fields, path)?;
+                true
+            }
+            ValueKind::Tuple => {
+                for (index, field) in self.fields.iter().enumerate() {
+                    field.check(&format!("{}.{}", path, index))?;
+                }
+                true
+            }
+            ValueKind::Option | ValueKind::Array => match self.fields.as_slice() {
+                [element] => {
+                    element.check(&format!("{}[]", path))?;
+                    true
+                }
+                _ => false,
+            },
+            _ => self.fields.is_empty(),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(Error::InvalidSchema(path.into()))
+        }
+    }
+
+    fn validate(&self, value: &Value, path: &str) -> Result<(), Error> {
+        if value.kind() != self.kind {
+            return Err(Error::TypeMismatch {
+                name: path.into(),
+                expected: self.kind,
+                found: value.kind(),
+            });
+        }
+        match (value, self.fields.as_slice()) {
+            (Value::Struct(items), fields) => validate_items(fields, items, path),
+            (Value::Tuple(values), fields) => {
+                if values.len() != fields.len() {
+                    return Err(Error::LengthMismatch {
+                        name: path.into(),
+                        expected: fields.len(),
+                        found: values.len(),
+                    });
+                }
+                for (index, (value, field)) in values.iter().zip(fields).enumerate() {
+                    field.validate(value, &format!("{}.{}", path, index))?;
+                }
+                Ok(())
+            }
+            (Value::Option(Some(value)), [element]) => {
+                element.validate(value, &format!("{}[]", path))
+            }
+            (Value::Array(values), [element]) => {
+                for (index, value) in values.iter().enumerate() {
+                    element.validate(value, &format!("{}[{}]", path, index))?;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn item_path(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.into()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+fn check_items(schemas: &[ItemSchema], path: &str) -> Result<(), Error> {
+    for (index, schema) in schemas.iter().enumerate() {
+        let name = item_path(path, &schema.name);
+        if schemas[..index]
+            .iter()
+            .any(|other| other.name == schema.name)
+        {
+            return Err(Error::DuplicateItem(name));
+        }
+        schema.check(&name)?;
+    }
+    Ok(())
+}
+
+fn validate_items(schemas: &[ItemSchema], items: &[MessageItem], path: &str) -> Result<(), Error> {
+    for (index, item) in items.iter().enumerate() {
+        let name = item_path(path, &item.name);
+        if items[..index].iter().any(|other| other.name == item.name) {
+            return Err(Error::DuplicateItem(name));
+        }
+        let schema = schemas
+            .iter()
+            .find(|schema| schema.name == item.name)
+            .ok_or_else(|| Error::UnexpectedItem(name.clone()))?;
+        schema.validate(&item.value, &name)?;
+    }
+    for schema in schemas.iter().filter(|schema| schema.required) {
+        if !items.iter().any(|item| item.name == schema.name) {
+            return Err(Error::MissingItem(item_path(path, &schema.name)));
+        }
+    }
+    Ok(())
+}
+
+/// The items a payload of an action is expected to carry.
+#[derive(Clone, PartialEq, BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
+pub struct PayloadSchema(pub Vec<ItemSchema>);
+
+impl PayloadSchema {
+    /// Checks the schema declares the contents of its compound items, and no item twice.
+    pub fn check(&self) -> Result<(), Error> {
+        check_items(&self.0, "")
+    }
+
+    /// Checks every required item is present, every item has the declared type, and there
+    /// are no undeclared or repeated items, down to the contents of compound values. Nested
+    /// items are named by their path in errors, e.g. `order.amounts[2]`.
+    pub fn validate(&self, payload: &Payload) -> Result<(), Error> {
+        validate_items(&self.0, &payload.0, "")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::boxed::Box;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn item(name: &str, kind: ValueKind, required: bool) -> ItemSchema {
+        ItemSchema {
+            name: name.to_string(),
+            kind,
+            required,
+            fields: Vec::new(),
+        }
+    }
+
+    fn compound(name: &str, kind: ValueKind, fields: Vec<ItemSchema>) -> ItemSchema {
+        ItemSchema {
+            fields,
+            ..item(name, kind, true)
+        }
+    }
+
+    fn field(name: &str, value: Value) -> MessageItem {
+        MessageItem {
+            name: name.to_string(),
+            value,
+        }
+    }
+
+    fn payload(items: Vec<MessageItem>) -> Payload {
+        Payload(items)
+    }
+
+    fn order_schema() -> PayloadSchema {
+        PayloadSchema(vec![
+            item("memo", ValueKind::String, false),
+            compound(
+                "order",
+                ValueKind::Struct,
+                vec![
+                    item("id", ValueKind::Uint64, true),
+                    compound(
+                        "amounts",
+                        ValueKind::Array,
+                        vec![item("amount", ValueKind::Uint128, true)],
+                    ),
+                    compound(
+                        "referrer",
+                        ValueKind::Option,
+                        vec![item("referrer", ValueKind::String, true)],
+                    ),
+                    compound(
+                        "pair",
+                        ValueKind::Tuple,
+                        vec![
+                            item("base", ValueKind::String, true),
+                            item("quote", ValueKind::String, true),
+                        ],
+                    ),
+                ],
+            ),
+        ])
+    }
+
+    fn order(amounts: Vec<Value>, referrer: Option<Value>, pair: Vec<Value>) -> Payload {
+        payload(vec![field(
+            "order",
+            Value::Struct(vec![
+                field("id", Value::Uint64(1)),
+                field("amounts", Value::Array(amounts)),
+                field("referrer", Value::Option(referrer.map(Box::new))),
+                field("pair", Value::Tuple(pair)),
+            ]),
+        )])
+    }
+
+    fn pair() -> Vec<Value> {
+        vec![
+            Value::String("NEAR".to_string()),
+            Value::String("USDT".to_string()),
+        ]
+    }
+
+    #[test]
+    fn flat_items() {
+        let schema = PayloadSchema(vec![
+            item("greeting", ValueKind::String, true),
+            item("count", ValueKind::Uint32, false),
+        ]);
+        let greeting = field("greeting", Value::String("hi".to_string()));
+        assert_eq!(schema.validate(&payload(vec![greeting.clone()])), Ok(()));
+        assert_eq!(
+            schema.validate(&payload(vec![field("count", Value::Uint32(1))])),
+            Err(Error::MissingItem("greeting".to_string()))
+        );
+        assert_eq!(
+            schema.validate(&payload(vec![
+                greeting.clone(),
+                field("x", Value::Bool(true))
+            ])),
+            Err(Error::UnexpectedItem("x".to_string()))
+        );
+        assert_eq!(
+            schema.validate(&payload(vec![greeting.clone(), greeting.clone()])),
+            Err(Error::DuplicateItem("greeting".to_string()))
+        );
+        assert_eq!(
+            schema.validate(&payload(vec![field("greeting", Value::Uint8(1))])),
+            Err(Error::TypeMismatch {
+                name: "greeting".to_string(),
+                expected: ValueKind::String,
+                found: ValueKind::Uint8,
+            })
+        );
+    }
+
+    #[test]
+    fn nested_values() {
+        let schema = order_schema();
+        assert_eq!(schema.check(), Ok(()));
+        let amounts = vec![Value::Uint128(1), Value::Uint128(2)];
+        let referrer = Some(Value::String("alice.near".to_string()));
+        assert_eq!(
+            schema.validate(&order(amounts.clone(), referrer.clone(), pair())),
+            Ok(())
+        );
+        assert_eq!(schema.validate(&order(Vec::new(), None, pair())), Ok(()));
+        assert_eq!(
+            schema.validate(&order(
+                vec![Value::Uint128(1), Value::Uint64(2)],
+                None,
+                pair()
+            )),
+            Err(Error::TypeMismatch {
+                name: "order.amounts[1]".to_string(),
+                expected: ValueKind::Uint128,
+                found: ValueKind::Uint64,
+            })
+        );
+        assert_eq!(
+            schema.validate(&order(amounts.clone(), Some(Value::Bool(true)), pair())),
+            Err(Error::TypeMismatch {
+                name: "order.referrer[]".to_string(),
+                expected: ValueKind::String,
+                found: ValueKind::Bool,
+            })
+        );
+        assert_eq!(
+            schema.validate(&order(amounts.clone(), None, pair()[..1].to_vec())),
+            Err(Error::LengthMismatch {
+                name: "order.pair".to_string(),
+                expected: 2,
+                found: 1,
+            })
+        );
+        assert_eq!(
+            schema.validate(&payload(vec![field(
+                "order",
+                Value::Struct(vec![field("amounts", Value::Array(amounts))]),
+            )])),
+            Err(Error::MissingItem("order.id".to_string()))
+        );
+    }
+
+    #[test]
+    fn borsh_round_trip() {
+        let schema = order_schema();
+        let encoded = schema.try_to_vec().unwrap();
+        assert_eq!(PayloadSchema::try_from_slice(&encoded).unwrap(), schema);
+    }
+
+    #[test]
+    fn check_schema() {
+        assert_eq!(
+            PayloadSchema(vec![item("list", ValueKind::Array, true)]).check(),
+            Err(Error::InvalidSchema("list".to_string()))
+        );
+        assert_eq!(
+            PayloadSchema(vec![compound(
+                "count",
+                ValueKind::Uint8,
+                vec![item("x", ValueKind::Uint8, true)]
+            )])
+            .check(),
+            Err(Error::InvalidSchema("count".to_string()))
+        );
+        assert_eq!(
+            PayloadSchema(vec![compound(
+                "order",
+                ValueKind::Struct,
+                vec![
+                    item("id", ValueKind::Uint64, true),
+                    item("id", ValueKind::Uint64, true)
+                ]
+            )])
+            .check(),
+            Err(Error::DuplicateItem("order.id".to_string()))
+        );
+    }
+}
@@ -0,0 +1,1164 @@
+use crate::big_int::{I256, U256};
+use crate::error::Error;
+use crate::hasher::Hasher;
+use crate::json_types::U128;
+use crate::schema::ValueKind;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use borsh::maybestd::io;
+use borsh::{BorshDeserialize, BorshSerialize};
+use core::convert::TryFrom;
+use serde::{Deserialize, Serialize};
+// use near_sdk::serde_json::{self, json, Value};
+// use crate::payload;
+
+#[derive(Clone, PartialEq, BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
+pub struct Content {
+    pub contract: Vec<u8>,
+    pub action: Vec<u8>,
+    pub data: Payload,
+}
+
+#[derive(Clone, PartialEq, BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
+pub struct SQoS {
+    pub t: u8,
+    pub v: Option<Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
+pub struct Session {
+    pub id: U128,
+    pub session_type: u8,
+    pub callback: Option<Vec<u8>>,
+    pub commitment: Option<Vec<u8>>,
+    pub answer: Option<Vec<u8>>,
+}
+
+impl Session {
+    /// Response to a request, `id` is the id of the request.
+    pub const RESPONSE: u8 = 1;
+    /// Request expecting a response sent to `callback`.
+    pub const REQUEST: u8 = 2;
+    /// Error response to a request, the payload is a `SessionError`.
+    pub const ERROR: u8 = 3;
+}
+
+/// Why a request failed, sent back to the requester in a `Session::ERROR` response.
+#[derive(Clone, PartialEq, BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
+pub struct SessionError {
+    pub code: u32,
+    pub message: String,
+}
+
+impl SessionError {
+    /// The handler of the request panicked.
+    pub const HANDLER_FAILED: u32 = 1;
+    /// The request failed and was dropped by the owner of the contract.
+    pub const DISCARDED: u32 = 2;
+    /// The request couldn't be sent by the cross-chain contract.
+    pub const SEND_FAILED: u32 = 3;
+
+    pub fn new(code: u32, message: &str) -> Self {
+        SessionError {
+            code,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn from_payload(payload: &Payload) -> Result<Self, Error> {
+        Ok(SessionError {
+            code: payload.get::<u32>("code")?,
+            message: payload.get::<String>("message")?,
+        })
+    }
+
+    pub fn into_payload(self) -> Payload {
+        let mut payload = Payload::new();
+        payload.set_item("code".to_string(), Value::Uint32(self.code));
+        payload.set_item("message".to_string(), Value::String(self.message));
+        payload
+    }
+}
+
+impl core::fmt::Display for SessionError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "error {}: {}", self.code, self.message)
+    }
+}
+
+#[derive(Clone, PartialEq, BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
+pub struct Message {
+    pub from_chain: String,
+    pub to_chain: String,
+    pub sender: Vec<u8>,
+    pub signer: Vec<u8>,
+    pub sqos: Vec<SQoS>,
+    pub content: Content,
+    pub session: Session,
+}
+
+#[derive(Clone, PartialEq, BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
+pub struct Context {
+    pub id: U128,
+    pub from_chain: String,
+    pub sender: Vec<u8>,
+    pub signer: Vec<u8>,
+    pub contract_id: String,
+    pub action: String,
+    pub sqos: Vec<SQoS>,
+    pub session: Session,
+}
+
+#[derive(Clone, PartialEq, BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
+#[serde(tag = "type")]
+pub struct DstContract {
+    pub contract_address: Vec<u8>,
+    pub action_name: Vec<u8>,
+}
+
+// #[derive(Clone, PartialEq, BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
+// // pub struct Field(Vec<Value>);
+
+// impl Field {
+//     pub fn new(vec: Vec<Value>) -> Field {
+//         Field(vec)
+//     }
+// }
+
+#[derive(Clone, PartialEq, BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
+pub struct Address(String, u8);
+
+impl Address {
+    pub fn new(address: String, address_type: u8) -> Self {
+        Address(address, address_type)
+    }
+
+    pub fn get(&self) -> String {
+        self.0.clone()
+    }
+
+    pub fn get_type(&self) -> u8 {
+        self.1
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub enum Value {
+    String(String),
+    Uint8(u8),
+    Uint16(u16),
+    Uint32(u32),
+    Uint64(u64),
+    Uint128(u128),
+    Int8(i8),
+    Int16(i16),
+    Int32(i32),
+    Int64(i64),
+    VecString(Vec<String>),
+    VecUint8(Vec<u8>),
+    VecUint16(Vec<u16>),
+    VecUint32(Vec<u32>),
+    VecUint64(Vec<u64>),
+    VecUint128(Vec<u128>),
+    VecInt8(Vec<i8>),
+    VecInt16(Vec<i16>),
+    VecInt32(Vec<i32>),
+    VecInt64(Vec<i64>),
+    Address(Address),
+    Bool(bool),
+    Bytes(Vec<u8>),
+    Bytes32([u8; 32]),
+    Int128(i128),
+    Option(Option<Box<Value>>),
+    Tuple(Vec<Value>),
+    Struct(Vec<MessageItem>),
+    Array(Vec<Value>),
+    Uint256(U256),
+    Int256(I256),
+}
+
+/// Reads and builds `Value::Bytes`, as `Vec<u8>` is taken by `Value::VecUint8`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Bytes(pub Vec<u8>);
+
+/// Reads and builds `Value::Tuple`, as `Vec<Value>` is taken by `Value::Array`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Tuple(pub Vec<Value>);
+
+// Borsh is implemented by hand for `Value`: the derive bounds every field type, which can't
+// be resolved for the recursive variants. The encoding is the same as the derived one.
+macro_rules! impl_borsh_for_value {
+    ($($index:literal => $name:ident,)*) => {
+        impl BorshSerialize for Value {
+            fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+                match self {
+                    $(Value::$name(value) => {
+                        BorshSerialize::serialize(&($index as u8), writer)?;
+                        BorshSerialize::serialize(value, writer)
+                    })*
+                }
+            }
+        }
+
+        impl BorshDeserialize for Value {
+            fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+                let index: u8 = BorshDeserialize::deserialize(buf)?;
+                match index {
+                    $($index => Ok(Value::$name(BorshDeserialize::deserialize(buf)?)),)*
+                    _ => Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Unexpected variant index: {}", index),
+                    )),
+                }
+            }
+        }
+    };
+}
+
+impl_borsh_for_value! {
+    0 => String,
+    1 => Uint8,
+    2 => Uint16,
+    3 => Uint32,
+    4 => Uint64,
+    5 => Uint128,
+    6 => Int8,
+    7 => Int16,
+    8 => Int32,
+    9 => Int64,
+    10 => VecString,
+    11 => VecUint8,
+    12 => VecUint16,
+    13 => VecUint32,
+    14 => VecUint64,
+    15 => VecUint128,
+    16 => VecInt8,
+    17 => VecInt16,
+    18 => VecInt32,
+    19 => VecInt64,
+    20 => Address,
+    21 => Bool,
+    22 => Bytes,
+    23 => Bytes32,
+    24 => Int128,
+    25 => Option,
+    26 => Tuple,
+    27 => Struct,
+    28 => Array,
+    29 => Uint256,
+    30 => Int256,
+}
+
+#[derive(Clone, PartialEq, BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
+pub struct MessageItem {
+    pub name: String,
+    pub value: Value,
+}
+
+/// Prefix of the names of the items the SDK adds to payloads itself.
+pub const RESERVED_ITEM_PREFIX: &str = "__";
+
+/// Name of the item carrying the sequence number of a message sent on an ordered channel.
+pub const SEQUENCE_ITEM: &str = "__sequence";
+
+#[derive(Clone, PartialEq, BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug)]
+pub struct Payload(pub(crate) Vec<MessageItem>);
+
+impl Payload {
+    pub fn new() -> Payload {
+        Payload(Vec::new())
+    }
+
+    /// Appends an item. Pushing the same item twice has no effect, but item names are unique
+    /// so pushing another value under an existing name panics; use `set_item` to replace it,
+    /// or `try_push_item` to handle the error.
+    pub fn push_item(&mut self, name: String, value: Value) {
+        if let Err(err) = self.try_push_item(name, value) {
+            panic!("{}", err);
+        }
+    }
+
+    /// Appends an item, or fails with `Error::DuplicateItem` if another value has the name.
+    pub fn try_push_item(&mut self, name: String, value: Value) -> Result<(), Error> {
+        match self.0.iter().find(|item| item.name == name) {
+            Some(item) if item.value == value => Ok(()),
+            Some(_) => Err(Error::DuplicateItem(name)),
+            None => {
+                self.0.push(MessageItem { name, value });
+                Ok(())
+            }
+        }
+    }
+
+    /// Checks item names are unique, which decoding a payload doesn't enforce.
+    pub fn check_unique_names(&self) -> Result<(), Error> {
+        for (index, item) in self.0.iter().enumerate() {
+            if self.0[..index].iter().any(|other| other.name == item.name) {
+                return Err(Error::DuplicateItem(item.name.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks no item uses a name reserved for the items of the SDK.
+    pub fn check_reserved_names(&self) -> Result<(), Error> {
+        match self
+            .0
+            .iter()
+            .find(|item| item.name.starts_with(RESERVED_ITEM_PREFIX))
+        {
+            Some(item) => Err(Error::ReservedItem(item.name.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// Inserts or replaces an item, returning the replaced value.
+    pub fn set_item(&mut self, name: String, value: Value) -> Option<Value> {
+        match self.0.iter_mut().find(|item| item.name == name) {
+            Some(item) => Some(core::mem::replace(&mut item.value, value)),
+            None => {
+                self.0.push(MessageItem { name, value });
+                None
+            }
+        }
+    }
+
+    pub fn remove_item(&mut self, name: &str) -> Option<Value> {
+        let index = self.0.iter().position(|item| item.name == name)?;
+        Some(self.0.remove(index).value)
+    }
+
+    pub fn get_item(&self, name: String) -> Option<Value> {
+        for item in self.0.iter() {
+            if item.name == name {
+                return Some(item.value.clone());
+            }
+        }
+        None
+    }
+
+    /// Reads an item as `T`, e.g. `payload.get::<Vec<u32>>("nums")`, cloning only the
+    /// content of the item.
+    pub fn get<T: ValueType>(&self, name: &str) -> Result<T::Type, Error> {
+        let item = self
+            .0
+            .iter()
+            .find(|item| item.name == name)
+            .ok_or_else(|| Error::MissingItem(name.to_string()))?;
+        T::get_value(&item.value).ok_or_else(|| Error::TypeMismatch {
+            name: item.name.clone(),
+            expected: T::kind(),
+            found: item.value.kind(),
+        })
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.iter().any(|item| item.name == name)
+    }
+
+    pub fn iter(&self) -> core::slice::Iter<'_, MessageItem> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn into_raw_data(&self) -> Vec<u8> {
+        let mut raw_bytes: Vec<u8> = Vec::new();
+        for item in self.0.iter() {
+            raw_bytes.extend(item.value.into_raw_data());
+        }
+        raw_bytes
+    }
+
+    /// The length of the Borsh encoding of the payload.
+    pub fn encoded_size(&self) -> usize {
+        encoded_size(self)
+    }
+}
+
+impl Default for Payload {
+    fn default() -> Self {
+        Payload::new()
+    }
+}
+
+/// Panics on repeated names with different values, like `push_item`.
+impl FromIterator<MessageItem> for Payload {
+    fn from_iter<I: IntoIterator<Item = MessageItem>>(iter: I) -> Self {
+        let mut payload = Payload::new();
+        for item in iter {
+            payload.push_item(item.name, item.value);
+        }
+        payload
+    }
+}
+
+impl FromIterator<(String, Value)> for Payload {
+    fn from_iter<I: IntoIterator<Item = (String, Value)>>(iter: I) -> Self {
+        iter.into_iter()
+            .map(|(name, value)| MessageItem { name, value })
+            .collect()
+    }
+}
+
+impl IntoIterator for Payload {
+    type Item = MessageItem;
+    type IntoIter = alloc::vec::IntoIter<MessageItem>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Payload {
+    type Item = &'a MessageItem;
+    type IntoIter = core::slice::Iter<'a, MessageItem>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl Content {
+    /// The length of the Borsh encoding of the content.
+    pub fn encoded_size(&self) -> usize {
+        encoded_size(self)
+    }
+}
+
+/// Counts the bytes written to it, to size an encoding without allocating it.
+struct SizeCounter(usize);
+
+impl io::Write for SizeCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn encoded_size<T: BorshSerialize>(value: &T) -> usize {
+    let mut counter = SizeCounter(0);
+    value.serialize(&mut counter).unwrap();
+    counter.0
+}
+
+impl Message {
+    pub fn to_hash<H: Hasher>(&self) -> String {
+        let message_serialized: Vec<u8> = self.try_to_vec().unwrap();
+        hex::encode(H::sha256(message_serialized.as_slice()))
+    }
+}
+
+impl Value {
+    pub fn get_value<T: ValueType>(&self) -> Option<T::Type> {
+        T::get_value(self)
+    }
+
+    pub fn into_raw_data(&self) -> Vec<u8> {
+        match self {
+            Value::String(value) => value.into_raw_data(),
+            Value::Uint8(value) => value.into_raw_data(),
+            Value::Uint16(value) => value.into_raw_data(),
+            Value::Uint32(value) => value.into_raw_data(),
+            Value::Uint64(value) => value.into_raw_data(),
+            Value::Uint128(value) => value.into_raw_data(),
+            Value::Int8(value) => value.into_raw_data(),
+            Value::Int16(value) => value.into_raw_data(),
+            Value::Int32(value) => value.into_raw_data(),
+            Value::Int64(value) => value.into_raw_data(),
+            Value::VecString(value) => value.into_raw_data(),
+            Value::VecUint8(value) => value.into_raw_data(),
+            Value::VecUint16(value) => value.into_raw_data(),
+            Value::VecUint32(value) => value.into_raw_data(),
+            Value::VecUint64(value) => value.into_raw_data(),
+            Value::VecUint128(value) => value.into_raw_data(),
+            Value::VecInt8(value) => value.into_raw_data(),
+            Value::VecInt16(value) => value.into_raw_data(),
+            Value::VecInt32(value) => value.into_raw_data(),
+            Value::VecInt64(value) => value.into_raw_data(),
+            Value::Address(value) => value.into_raw_data(),
+            Value::Bool(value) => value.into_raw_data(),
+            Value::Bytes(value) => value.clone(),
+            Value::Bytes32(value) => value.into_raw_data(),
+            Value::Int128(value) => value.into_raw_data(),
+            Value::Option(value) => match value {
+                Some(value) => [&[1u8][..], &value.into_raw_data()].concat(),
+                None => Vec::from([0u8]),
+            },
+            Value::Tuple(values) | Value::Array(values) => values.into_raw_data(),
+            Value::Struct(items) => items.into_raw_data(),
+            Value::Uint256(value) => value.into_raw_data(),
+            Value::Int256(value) => value.into_raw_data(),
+        }
+    }
+}
+
+pub trait ValueType {
+    type Type;
+    fn kind() -> ValueKind;
+    fn get_value(type_value: &Value) -> Option<Self::Type>;
+    // Named before clippy's convention, kept as it's public.
+    #[allow(clippy::wrong_self_convention)]
+    fn into_raw_data(&self) -> Vec<u8>;
+}
+
+impl ValueType for String {
+    type Type = String;
+    fn kind() -> ValueKind {
+        ValueKind::String
+    }
+    fn get_value(type_value: &Value) -> Option<Self::Type> {
+        if let Value::String(val) = type_value {
+            Some(val.clone())
+        } else {
+            None
+        }
+    }
+    fn into_raw_data(&self) -> Vec<u8> {
+        Vec::from(self.as_bytes())
+    }
+}
+
+impl ValueType for Vec<String> {
+    type Type = Vec<String>;
+    fn kind() -> ValueKind {
+        ValueKind::VecString
+    }
+    fn get_value(type_value: &Value) -> Option<Self::Type> {
+        if let Value::VecString(val) = type_value {
+            Some(val.clone())
+        } else {
+            None
+        }
+    }
+    fn into_raw_data(&self) -> Vec<u8> {
+        let mut raw_bytes = Vec::new();
+        for value in self.iter() {
+            raw_bytes.extend(value.as_bytes());
+        }
+        raw_bytes
+    }
+}
+
+impl ValueType for Address {
+    type Type = Address;
+    fn kind() -> ValueKind {
+        ValueKind::Address
+    }
+    fn get_value(type_value: &Value) -> Option<Self::Type> {
+        if let Value::Address(val) = type_value {
+            Some(val.clone())
+        } else {
+            None
+        }
+    }
+    fn into_raw_data(&self) -> Vec<u8> {
+        Vec::from(self.0.as_bytes())
+    }
+}
+
+impl ValueType for bool {
+    type Type = bool;
+    fn kind() -> ValueKind {
+        ValueKind::Bool
+    }
+    fn get_value(type_value: &Value) -> Option<Self::Type> {
+        if let Value::Bool(val) = type_value {
+            Some(*val)
+        } else {
+            None
+        }
+    }
+    fn into_raw_data(&self) -> Vec<u8> {
+        Vec::from([*self as u8])
+    }
+}
+
+impl ValueType for Bytes {
+    type Type = Vec<u8>;
+    fn kind() -> ValueKind {
+        ValueKind::Bytes
+    }
+    fn get_value(type_value: &Value) -> Option<Self::Type> {
+        if let Value::Bytes(val) = type_value {
+            Some(val.clone())
+        } else {
+            None
+        }
+    }
+    fn into_raw_data(&self) -> Vec<u8> {
+        self.0.clone()
+    }
+}
+
+impl ValueType for [u8; 32] {
+    type Type = [u8; 32];
+    fn kind() -> ValueKind {
+        ValueKind::Bytes32
+    }
+    fn get_value(type_value: &Value) -> Option<Self::Type> {
+        if let Value::Bytes32(val) = type_value {
+            Some(*val)
+        } else {
+            None
+        }
+    }
+    fn into_raw_data(&self) -> Vec<u8> {
+        self.to_vec()
+    }
+}
+
+impl ValueType for Option<Value> {
+    type Type = Option<Value>;
+    fn kind() -> ValueKind {
+        ValueKind::Option
+    }
+    fn get_value(type_value: &Value) -> Option<Self::Type> {
+        if let Value::Option(val) = type_value {
+            Some(val.as_ref().map(|value| value.as_ref().clone()))
+        } else {
+            None
+        }
+    }
+    fn into_raw_data(&self) -> Vec<u8> {
+        Value::Option(self.clone().map(Box::new)).into_raw_data()
+    }
+}
+
+impl ValueType for Tuple {
+    type Type = Vec<Value>;
+    fn kind() -> ValueKind {
+        ValueKind::Tuple
+    }
+    fn get_value(type_value: &Value) -> Option<Self::Type> {
+        if let Value::Tuple(val) = type_value {
+            Some(val.clone())
+        } else {
+            None
+        }
+    }
+    fn into_raw_data(&self) -> Vec<u8> {
+        self.0.into_raw_data()
+    }
+}
+
+impl ValueType for Vec<Value> {
+    type Type = Vec<Value>;
+    fn kind() -> ValueKind {
+        ValueKind::Array
+    }
+    fn get_value(type_value: &Value) -> Option<Self::Type> {
+        if let Value::Array(val) = type_value {
+            Some(val.clone())
+        } else {
+            None
+        }
+    }
+    fn into_raw_data(&self) -> Vec<u8> {
+        let mut raw_bytes = Vec::new();
+        for value in self.iter() {
+            raw_bytes.extend(value.into_raw_data());
+        }
+        raw_bytes
+    }
+}
+
+impl ValueType for Vec<MessageItem> {
+    type Type = Vec<MessageItem>;
+    fn kind() -> ValueKind {
+        ValueKind::Struct
+    }
+    fn get_value(type_value: &Value) -> Option<Self::Type> {
+        if let Value::Struct(val) = type_value {
+            Some(val.clone())
+        } else {
+            None
+        }
+    }
+    fn into_raw_data(&self) -> Vec<u8> {
+        let mut raw_bytes = Vec::new();
+        for item in self.iter() {
+            raw_bytes.extend(item.value.into_raw_data());
+        }
+        raw_bytes
+    }
+}
+
+impl ValueType for U256 {
+    type Type = U256;
+    fn kind() -> ValueKind {
+        ValueKind::Uint256
+    }
+    fn get_value(type_value: &Value) -> Option<Self::Type> {
+        if let Value::Uint256(val) = type_value {
+            Some(*val)
+        } else {
+            None
+        }
+    }
+    fn into_raw_data(&self) -> Vec<u8> {
+        self.to_be_bytes().to_vec()
+    }
+}
+
+impl ValueType for I256 {
+    type Type = I256;
+    fn kind() -> ValueKind {
+        ValueKind::Int256
+    }
+    fn get_value(type_value: &Value) -> Option<Self::Type> {
+        if let Value::Int256(val) = type_value {
+            Some(*val)
+        } else {
+            None
+        }
+    }
+    fn into_raw_data(&self) -> Vec<u8> {
+        self.to_be_bytes().to_vec()
+    }
+}
+
+macro_rules! def_int_values {
+    ($($name:ident($repr:ty),)*) => {$(
+        impl ValueType for $repr {
+            type Type = $repr;
+            fn kind() -> ValueKind {
+                ValueKind::$name
+            }
+            fn get_value(type_value: &Value) -> Option<Self::Type> {
+                if let Value::$name(val) = type_value {
+                    Some(*val)
+                } else {
+                    None
+                }
+            }
+            fn into_raw_data(&self) -> Vec<u8> {
+                self.to_be_bytes().to_vec()
+            }
+        }
+    )*}
+}
+
+macro_rules! def_vec_int_values {
+    ($($name:ident($repr:ty),)*) => {$(
+        impl ValueType for $repr {
+            type Type = $repr;
+            fn kind() -> ValueKind {
+                ValueKind::$name
+            }
+            fn get_value(type_value: &Value) -> Option<Self::Type> {
+                if let Value::$name(val) = type_value {
+                    Some(val.clone())
+                } else {
+                    None
+                }
+            }
+
+            fn into_raw_data(&self) -> Vec<u8> {
+                let mut raw_bytes = Vec::new();
+                for value in self.iter() {
+                    raw_bytes.extend(value.to_be_bytes().to_vec());
+                }
+                raw_bytes
+            }
+        }
+    )*}
+}
+
+def_int_values! {
+    Uint8(u8),
+    Uint16(u16),
+    Uint32(u32),
+    Uint64(u64),
+    Uint128(u128),
+    Int8(i8),
+    Int16(i16),
+    Int32(i32),
+    Int64(i64),
+    Int128(i128),
+}
+
+def_vec_int_values! {
+    VecUint8(Vec<u8>),
+    VecUint16(Vec<u16>),
+    VecUint32(Vec<u32>),
+    VecUint64(Vec<u64>),
+    VecUint128(Vec<u128>),
+    VecInt8(Vec<i8>),
+    VecInt16(Vec<i16>),
+    VecInt32(Vec<i32>),
+    VecInt64(Vec<i64>),
+}
+
+macro_rules! def_value_conversions {
+    ($($name:ident($repr:ty),)*) => {$(
+        impl From<$repr> for Value {
+            fn from(value: $repr) -> Self {
+                Value::$name(value)
+            }
+        }
+
+        impl TryFrom<Value> for $repr {
+            type Error = Error;
+            fn try_from(value: Value) -> Result<Self, Self::Error> {
+                match value {
+                    Value::$name(value) => Ok(value),
+                    value => Err(Error::InvalidValue {
+                        expected: ValueKind::$name,
+                        found: value.kind(),
+                    }),
+                }
+            }
+        }
+    )*}
+}
+
+def_value_conversions! {
+    String(String),
+    Uint8(u8),
+    Uint16(u16),
+    Uint32(u32),
+    Uint64(u64),
+    Uint128(u128),
+    Int8(i8),
+    Int16(i16),
+    Int32(i32),
+    Int64(i64),
+    VecString(Vec<String>),
+    VecUint8(Vec<u8>),
+    VecUint16(Vec<u16>),
+    VecUint32(Vec<u32>),
+    VecUint64(Vec<u64>),
+    VecUint128(Vec<u128>),
+    VecInt8(Vec<i8>),
+    VecInt16(Vec<i16>),
+    VecInt32(Vec<i32>),
+    VecInt64(Vec<i64>),
+    Address(Address),
+    Bool(bool),
+    Bytes32([u8; 32]),
+    Int128(i128),
+    Struct(Vec<MessageItem>),
+    Array(Vec<Value>),
+    Uint256(U256),
+    Int256(I256),
+}
+
+impl From<Bytes> for Value {
+    fn from(value: Bytes) -> Self {
+        Value::Bytes(value.0)
+    }
+}
+
+impl TryFrom<Value> for Bytes {
+    type Error = Error;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Bytes(value) => Ok(Bytes(value)),
+            value => Err(Error::InvalidValue {
+                expected: ValueKind::Bytes,
+                found: value.kind(),
+            }),
+        }
+    }
+}
+
+impl From<Tuple> for Value {
+    fn from(value: Tuple) -> Self {
+        Value::Tuple(value.0)
+    }
+}
+
+impl TryFrom<Value> for Tuple {
+    type Error = Error;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Tuple(value) => Ok(Tuple(value)),
+            value => Err(Error::InvalidValue {
+                expected: ValueKind::Tuple,
+                found: value.kind(),
+            }),
+        }
+    }
+}
+
+impl From<U128> for Value {
+    fn from(value: U128) -> Self {
+        Value::Uint128(value.0)
+    }
+}
+
+impl TryFrom<Value> for U128 {
+    type Error = Error;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        u128::try_from(value).map(U128)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+impl From<Option<Value>> for Value {
+    fn from(value: Option<Value>) -> Self {
+        Value::Option(value.map(Box::new))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn item(name: &str, value: Value) -> MessageItem {
+        MessageItem {
+            name: name.to_string(),
+            value,
+        }
+    }
+
+    #[test]
+    fn push_and_set_items() {
+        let mut payload = Payload::new();
+        payload.push_item("a".to_string(), Value::Uint8(1));
+        // The same item again is ignored.
+        payload.push_item("a".to_string(), Value::Uint8(1));
+        assert_eq!(payload.len(), 1);
+        assert_eq!(
+            payload.try_push_item("a".to_string(), Value::Uint8(2)),
+            Err(Error::DuplicateItem("a".to_string()))
+        );
+        assert_eq!(
+            payload.set_item("a".to_string(), Value::Uint8(2)),
+            Some(Value::Uint8(1))
+        );
+        assert_eq!(payload.set_item("b".to_string(), Value::Bool(true)), None);
+        assert_eq!(payload.get::<u8>("a"), Ok(2));
+        assert_eq!(payload.remove_item("b"), Some(Value::Bool(true)));
+        assert!(!payload.contains("b"));
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate item a")]
+    fn push_item_twice() {
+        let mut payload = Payload::new();
+        payload.push_item("a".to_string(), Value::Uint8(1));
+        payload.push_item("a".to_string(), Value::Uint8(2));
+    }
+
+    #[test]
+    fn get_item_errors() {
+        let payload: Payload = vec![("a".to_string(), Value::Uint8(1))]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            payload.get::<u8>("b"),
+            Err(Error::MissingItem("b".to_string()))
+        );
+        assert_eq!(
+            payload.get::<String>("a"),
+            Err(Error::TypeMismatch {
+                name: "a".to_string(),
+                expected: ValueKind::String,
+                found: ValueKind::Uint8,
+            })
+        );
+    }
+
+    #[test]
+    fn check_names() {
+        let payload = Payload(vec![item("a", Value::Uint8(1)), item("a", Value::Uint8(2))]);
+        assert_eq!(
+            payload.check_unique_names(),
+            Err(Error::DuplicateItem("a".to_string()))
+        );
+        let payload: Payload = vec![item("__a", Value::Uint8(1))].into_iter().collect();
+        assert_eq!(
+            payload.check_reserved_names(),
+            Err(Error::ReservedItem("__a".to_string()))
+        );
+    }
+
+    #[test]
+    fn iterate_items() {
+        let items = vec![item("a", Value::Uint8(1)), item("b", Value::Bool(true))];
+        let payload: Payload = items.clone().into_iter().collect();
+        assert_eq!((&payload).into_iter().cloned().collect::<Vec<_>>(), items);
+        assert_eq!(payload.iter().count(), 2);
+        assert_eq!(payload.into_iter().collect::<Vec<_>>(), items);
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate item a")]
+    fn collect_repeated_names() {
+        let _: Payload = vec![item("a", Value::Uint8(1)), item("a", Value::Uint8(2))]
+            .into_iter()
+            .collect();
+    }
+
+    fn nested_value() -> Value {
+        Value::Struct(vec![
+            item("id", Value::Uint64(7)),
+            item(
+                "orders",
+                Value::Array(vec![Value::Tuple(vec![
+                    Value::String("NEAR".to_string()),
+                    Value::Uint256(U256::from(1_000u64)),
+                    Value::Option(Some(Box::new(Value::Int256(I256::from(-5i128))))),
+                ])]),
+            ),
+            item("memo", Value::Option(None)),
+            item("hash", Value::Bytes32([9; 32])),
+        ])
+    }
+
+    #[test]
+    fn value_borsh_round_trip() {
+        let value = nested_value();
+        let encoded = value.try_to_vec().unwrap();
+        assert_eq!(Value::try_from_slice(&encoded).unwrap(), value);
+        let payload: Payload = vec![item("order", value)].into_iter().collect();
+        let encoded = payload.try_to_vec().unwrap();
+        assert_eq!(encoded.len(), payload.encoded_size());
+        assert_eq!(Payload::try_from_slice(&encoded).unwrap(), payload);
+    }
+
+    #[test]
+    fn encoded_size_matches_encoding() {
+        assert_eq!(Payload::new().encoded_size(), 4);
+        let content = Content {
+            contract: vec![1, 2],
+            action: vec![3],
+            data: vec![item("order", nested_value())].into_iter().collect(),
+        };
+        assert_eq!(content.encoded_size(), content.try_to_vec().unwrap().len());
+        assert_eq!(
+            content.data.encoded_size(),
+            content.data.try_to_vec().unwrap().len()
+        );
+    }
+
+    #[test]
+    fn value_borsh_encoding() {
+        let value = Value::Option(Some(Box::new(Value::Uint8(7))));
+        assert_eq!(value.try_to_vec().unwrap(), vec![25, 1, 1, 7]);
+        assert_eq!(
+            Value::Array(vec![Value::Bool(true)]).try_to_vec().unwrap(),
+            vec![28, 1, 0, 0, 0, 21, 1]
+        );
+        assert!(Value::try_from_slice(&[31]).is_err());
+        // A truncated nested value fails instead of reading past the end.
+        assert!(Value::try_from_slice(&[28, 2, 0, 0, 0, 21, 1]).is_err());
+    }
+
+    #[test]
+    fn raw_data() {
+        assert_eq!(Value::Uint16(0x0102).into_raw_data(), vec![1, 2]);
+        assert_eq!(Value::Option(None).into_raw_data(), vec![0]);
+        assert_eq!(
+            Value::Option(Some(Box::new(Value::Int8(-1)))).into_raw_data(),
+            vec![1, 0xff]
+        );
+        assert_eq!(
+            Value::Struct(vec![
+                item("a", Value::String("ab".to_string())),
+                item(
+                    "b",
+                    Value::Tuple(vec![Value::Bool(true), Value::VecUint16(vec![3])])
+                ),
+            ])
+            .into_raw_data(),
+            vec![b'a', b'b', 1, 0, 3]
+        );
+        let raw = nested_value().into_raw_data();
+        assert_eq!(&raw[..8], &7u64.to_be_bytes());
+        assert_eq!(&raw[raw.len() - 32..], &[9; 32]);
+    }
+
+    #[test]
+    fn value_conversions() {
+        assert_eq!(u32::try_from(Value::from(5u32)), Ok(5));
+        assert_eq!(
+            Vec::<String>::try_from(Value::from(vec!["a".to_string()])),
+            Ok(vec!["a".to_string()])
+        );
+        assert_eq!(U128::try_from(Value::from(U128(9))), Ok(U128(9)));
+        assert_eq!(
+            Bytes::try_from(Value::from(Bytes(vec![1, 2]))),
+            Ok(Bytes(vec![1, 2]))
+        );
+        assert_eq!(
+            Tuple::try_from(Value::from(Tuple(vec![Value::Bool(false)]))),
+            Ok(Tuple(vec![Value::Bool(false)]))
+        );
+        assert_eq!(
+            Value::from(Some(Value::from("x"))),
+            Value::Option(Some(Box::new(Value::String("x".to_string()))))
+        );
+        let nested = nested_value();
+        assert_eq!(
+            Value::from(Vec::<MessageItem>::try_from(nested.clone()).unwrap()),
+            nested
+        );
+    }
+
+    #[test]
+    fn failing_conversions() {
+        assert_eq!(
+            u32::try_from(Value::Uint64(5)),
+            Err(Error::InvalidValue {
+                expected: ValueKind::Uint32,
+                found: ValueKind::Uint64,
+            })
+        );
+        assert_eq!(
+            Vec::<u8>::try_from(Value::Bytes(vec![1])),
+            Err(Error::InvalidValue {
+                expected: ValueKind::VecUint8,
+                found: ValueKind::Bytes,
+            })
+        );
+        assert_eq!(
+            Bytes::try_from(Value::VecUint8(vec![1])),
+            Err(Error::InvalidValue {
+                expected: ValueKind::Bytes,
+                found: ValueKind::VecUint8,
+            })
+        );
+        assert_eq!(
+            Tuple::try_from(Value::Array(Vec::new())),
+            Err(Error::InvalidValue {
+                expected: ValueKind::Tuple,
+                found: ValueKind::Array,
+            })
+        );
+        assert_eq!(
+            Vec::<MessageItem>::try_from(Value::Option(None)),
+            Err(Error::InvalidValue {
+                expected: ValueKind::Struct,
+                found: ValueKind::Option,
+            })
+        );
+        assert_eq!(
+            U128::try_from(Value::Int128(1)),
+            Err(Error::InvalidValue {
+                expected: ValueKind::Uint128,
+                found: ValueKind::Int128,
+            })
+        );
+    }
+}